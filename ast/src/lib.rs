@@ -1,12 +1,36 @@
 use core::fmt;
-use std::rc::Rc;
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::{Rc, Weak};
 
 pub type Identifier = String;
 
+/// The location of a node in its source file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    /// Byte offset of the first character.
+    pub start: usize,
+    /// Byte offset after the last character.
+    pub end: usize,
+    /// Line of the first character, starting at 1.
+    pub line: usize,
+    /// Column of the first character, starting at 1.
+    pub column: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Namespace {
     pub identifier: Identifier,
     pub entities: Vec<Rc<Entity>>,
+    pub span: Span,
+    /// The file the namespace is declared in, if it was parsed from a file.
+    pub source: Option<PathBuf>,
 }
 
 impl Namespace {
@@ -19,36 +43,166 @@ impl Namespace {
         }
     }
 
-    pub fn new(identifier: Identifier) -> Namespace { Namespace { identifier, entities: vec![] } }
+    pub fn new(identifier: Identifier) -> Namespace {
+        Namespace { identifier, entities: vec![], span: Span::default(), source: None }
+    }
 
     pub fn add_entity(&mut self, entity: Rc<Entity>) { self.entities.push(entity) }
+
+    /// Describes where `span` is located in the source of this namespace, e.g. `model.pkn:12:5`.
+    pub fn locate(&self, span: Span) -> String {
+        match &self.source {
+            Some(source) => format!("{}:{}", source.display(), span),
+            None => span.to_string(),
+        }
+    }
+}
+
+/// All namespaces of a model, including the ones imported from other files.
+#[derive(Debug, Clone, Default)]
+pub struct Package {
+    pub namespaces: Vec<Namespace>,
+}
+
+impl Package {
+    pub fn new() -> Package { Package { namespaces: vec![] } }
+
+    pub fn add_namespace(&mut self, namespace: Namespace) { self.namespaces.push(namespace) }
+
+    pub fn find_namespace(&self, identifier: &str) -> Option<&Namespace> {
+        self.namespaces.iter().find(|n| n.identifier == identifier)
+    }
+
+    /// Finds an entity by its qualified name, e.g. `org.billing.Invoice`.
+    pub fn find_entity(&self, qualified: &str) -> Option<Rc<Entity>> {
+        let (namespace, identifier) = split_qualified(qualified)?;
+        self.find_namespace(namespace)?.find_entity(identifier.to_owned())
+    }
+
+    /// Returns the entities of all namespaces.
+    pub fn entities(&self) -> Vec<Rc<Entity>> {
+        self.namespaces.iter().flat_map(|n| n.entities.iter().cloned()).collect()
+    }
+}
+
+/// Splits a qualified name like `org.billing.Invoice` into the namespace `org.billing` and the
+/// entity identifier `Invoice`. Returns `None` for unqualified names.
+pub fn split_qualified(qualified: &str) -> Option<(&str, &str)> {
+    let index = qualified.rfind('.')?;
+    Some((&qualified[.. index], &qualified[index + 1 ..]))
 }
 
 pub trait Identifying {
     fn identifier(&self) -> Identifier;
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Scalar {
     String,
     Integer,
     Double,
     Boolean,
     Character,
+    /// A calendar day without a time.
+    Date,
+    /// A point in time.
+    DateTime,
+    Uuid,
+    /// A decimal number without rounding errors, e.g. for amounts of money.
+    Decimal,
+}
+
+impl Scalar {
+    /// The scalars every namespace provides.
+    pub const BUILT_IN: [Scalar; 9] = [
+        Scalar::String,
+        Scalar::Boolean,
+        Scalar::Character,
+        Scalar::Double,
+        Scalar::Integer,
+        Scalar::Date,
+        Scalar::DateTime,
+        Scalar::Uuid,
+        Scalar::Decimal,
+    ];
 }
 
 impl fmt::Display for Scalar {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{:?}", self) }
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Scalar::Uuid => write!(f, "UUID"),
+            scalar => write!(f, "{:?}", scalar),
+        }
+    }
+}
+
+/// A scalar declared in the model, e.g. `scalar Email : String`. Its values are the ones of the
+/// built-in scalar it is based on.
+#[derive(Debug, Clone)]
+pub struct CustomScalar {
+    pub name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub base: Reference,
+    pub span: Span,
+}
+
+impl Identifying for CustomScalar {
+    fn identifier(&self) -> String { self.name.clone() }
 }
 
 impl Identifying for Scalar {
     fn identifier(&self) -> String { self.to_string() }
 }
 
+/// A type whose values are the values of one of its members, e.g.
+/// `union SearchResult = Person | Vehicle`.
+#[derive(Debug, Clone)]
+pub struct Union {
+    pub name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub members: Vec<Reference>,
+    pub span: Span,
+}
+
+impl Identifying for Union {
+    fn identifier(&self) -> String { self.name.clone() }
+}
+
 #[derive(Debug, Clone)]
 pub struct Enum {
     pub identifier: Identifier,
-    pub values: Vec<(String, Option<usize>)>,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub members: Vec<EnumMember>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnumMember {
+    pub name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub value: Option<EnumValue>,
+    pub span: Span,
+}
+
+/// The value a member of an enum stands for, e.g. `-1` of `unknown = -1` or `"red"` of
+/// `red = "red"`. The members of an enum either all have integer or all have string values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnumValue {
+    Integer(i64),
+    String(String),
+}
+
+impl fmt::Display for EnumValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EnumValue::Integer(integer) => write!(f, "{}", integer),
+            EnumValue::String(string) => write!(f, "\"{}\"", string),
+        }
+    }
 }
 
 impl Identifying for Enum {
@@ -58,20 +212,373 @@ impl Identifying for Enum {
 #[derive(Debug, Clone)]
 pub struct Structure {
     pub name: String,
-    pub parent: Option<Rc<Entity>>,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub kind: StructureKind,
+    /// The type parameters of a generic structure, e.g. `T` of `Page<T>`. Each is an
+    /// `Entity::TypeParameter` only the features of the structure can reference.
+    pub parameters: Vec<Rc<Entity>>,
+    pub parent: Option<Reference>,
+    /// The interfaces the structure implements, e.g. `Named` of `Person implements Named`.
+    pub interfaces: Vec<Reference>,
     pub attributes: Vec<Attribute>,
     pub operations: Vec<Operation>,
+    pub span: Span,
+}
+
+/// What a structure can be used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StructureKind {
+    #[default]
+    Concrete,
+    /// `abstract`, a structure which is only used as the parent of other structures.
+    Abstract,
+    /// `interface`, features other structures promise to provide.
+    Interface,
+    /// `event`, something which happened in the domain, emitted by operations.
+    Event,
+}
+
+impl fmt::Display for StructureKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StructureKind::Concrete => Ok(()),
+            StructureKind::Abstract => write!(f, "abstract"),
+            StructureKind::Interface => write!(f, "interface"),
+            StructureKind::Event => write!(f, "event"),
+        }
+    }
+}
+
+impl Structure {
+    pub fn is_interface(&self) -> bool { self.kind == StructureKind::Interface }
+
+    pub fn is_abstract(&self) -> bool { self.kind == StructureKind::Abstract }
+
+    pub fn is_event(&self) -> bool { self.kind == StructureKind::Event }
+
+    pub fn is_generic(&self) -> bool { !self.parameters.is_empty() }
+
+    /// Looks up the type parameter `name` of the structure.
+    pub fn find_parameter(&self, name: &str) -> Option<Rc<Entity>> {
+        self.parameters.iter().find(|p| p.identifier() == name).cloned()
+    }
+
+    /// The structure a generic structure becomes for the type `arguments`, e.g. `Page<Order>`,
+    /// with every reference to a type parameter replaced by its argument. The instance keeps the
+    /// name of the generic structure.
+    pub fn instantiate(&self, arguments: &[Reference]) -> Structure {
+        let substitute = |reference: &Reference| reference.substitute(&self.parameters, arguments);
+        let mut instance = self.clone();
+        instance.parameters = vec![];
+        for attr in instance.attributes.iter_mut() {
+            attr.entity = substitute(&attr.entity);
+        }
+        for op in instance.operations.iter_mut() {
+            for param in op.parameter.iter_mut() {
+                param.entity = substitute(&param.entity);
+            }
+            op.returns = op.returns.as_ref().map(substitute);
+        }
+        instance
+    }
+
+    /// The structures the structure inherits from, closest first. Stops at cycles and at parents
+    /// which are not structures.
+    pub fn ancestors(&self) -> Vec<Rc<Entity>> {
+        let mut ancestors: Vec<Rc<Entity>> = vec![];
+        let mut current = self.parent.as_ref().and_then(Reference::get);
+        while let Some(entity) = current {
+            let struc = match entity.as_ref() {
+                Entity::Structure(struc) => struc,
+                _ => break,
+            };
+            if std::ptr::eq(struc, self) || ancestors.iter().any(|a| Rc::ptr_eq(a, &entity)) {
+                break;
+            }
+            current = struc.parent.as_ref().and_then(Reference::get);
+            ancestors.push(entity);
+        }
+        ancestors
+    }
+
+    /// Every interface the structure and its ancestors implement, including the interfaces those
+    /// interfaces implement.
+    pub fn implemented(&self) -> Vec<Rc<Entity>> {
+        let interfaces = |struc: &Structure| -> Vec<Rc<Entity>> {
+            struc.interfaces.iter().filter_map(Reference::get).collect()
+        };
+        let mut pending = interfaces(self);
+        for ancestor in self.ancestors() {
+            if let Entity::Structure(struc) = ancestor.as_ref() {
+                pending.extend(interfaces(struc));
+            }
+        }
+        let mut implemented: Vec<Rc<Entity>> = vec![];
+        let mut index = 0;
+        while index < pending.len() {
+            let interface = Rc::clone(&pending[index]);
+            index += 1;
+            if implemented.iter().any(|i| Rc::ptr_eq(i, &interface)) {
+                continue;
+            }
+            if let Entity::Structure(struc) = interface.as_ref() {
+                pending.extend(interfaces(struc));
+            }
+            implemented.push(interface);
+        }
+        implemented
+    }
+
+    /// The attributes of the structure including the inherited ones, those of the root ancestor
+    /// first. An attribute redeclared by a descendant overrides the inherited one in its place,
+    /// e.g. `tires: Tire [2, 4]` of `Bike : Vehicle` replaces `tires: Tire [+]` of `Vehicle`.
+    /// Attributes of implemented interfaces which are not provided otherwise come last.
+    pub fn effective_attributes(&self) -> Vec<Attribute> {
+        self.effective(|struc| &struc.attributes, |attr| attr.name.clone())
+    }
+
+    /// The operations of the structure including the inherited ones, ordered and overridden like
+    /// [`Structure::effective_attributes`]. An operation only overrides an inherited one with the
+    /// same name and parameter types, overloads are kept side by side.
+    pub fn effective_operations(&self) -> Vec<Operation> {
+        self.effective(|struc| &struc.operations, Operation::signature)
+    }
+
+    fn effective<T: Clone>(
+        &self, features: fn(&Structure) -> &Vec<T>, key: fn(&T) -> String,
+    ) -> Vec<T> {
+        let ancestors = self.ancestors();
+        let mut lineage: Vec<&Structure> = ancestors
+            .iter()
+            .rev()
+            .filter_map(|a| match a.as_ref() {
+                Entity::Structure(struc) => Some(struc),
+                _ => None,
+            })
+            .collect();
+        lineage.push(self);
+
+        let mut effective: Vec<T> = vec![];
+        for struc in lineage {
+            let inherited = effective.len();
+            for feature in features(struc) {
+                match effective[.. inherited].iter().position(|f| key(f) == key(feature)) {
+                    Some(index) => effective[index] = feature.clone(),
+                    None => effective.push(feature.clone()),
+                }
+            }
+        }
+        for interface in self.implemented() {
+            if let Entity::Structure(struc) = interface.as_ref() {
+                for feature in features(struc) {
+                    if effective.iter().all(|f| key(f) != key(feature)) {
+                        effective.push(feature.clone());
+                    }
+                }
+            }
+        }
+        effective
+    }
+
+    /// The key attributes identifying the instances of the structure, including inherited ones.
+    /// More than one make up a composite key.
+    pub fn keys(&self) -> Vec<Attribute> {
+        self.effective_attributes().into_iter().filter(|attr| attr.key.is_some()).collect()
+    }
+
+    /// Returns every reference to another entity used by this structure.
+    pub fn references(&self) -> Vec<&Reference> {
+        let mut references = vec![];
+        if let Some(parent) = &self.parent {
+            references.push(parent);
+        }
+        references.extend(self.interfaces.iter());
+        for attr in &self.attributes {
+            references.push(&attr.entity);
+        }
+        references.extend(self.operations.iter().flat_map(Operation::references));
+        with_arguments(references)
+    }
+}
+
+/// Adds the type arguments of `references` to them, e.g. `Order` of `Page<Order>`, as they
+/// refer to entities as well.
+fn with_arguments(mut references: Vec<&Reference>) -> Vec<&Reference> {
+    let mut index = 0;
+    while index < references.len() {
+        let reference = references[index];
+        references.extend(reference.arguments.iter());
+        index += 1;
+    }
+    references
+}
+
+/// Operations which are not features of a structure, e.g.
+/// `service OrderService { place(order: Order): Receipt }`.
+#[derive(Debug, Clone)]
+pub struct Service {
+    pub name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub operations: Vec<Operation>,
+    pub span: Span,
+}
+
+impl Service {
+    /// Returns every reference to an entity used by the operations of the service.
+    pub fn references(&self) -> Vec<&Reference> {
+        with_arguments(self.operations.iter().flat_map(Operation::references).collect())
+    }
+}
+
+impl Identifying for Service {
+    fn identifier(&self) -> String { self.name.clone() }
 }
 
 impl Identifying for Structure {
     fn identifier(&self) -> String { self.name.clone() }
 }
 
+/// A reference to an entity of a namespace.
+///
+/// References are created by name and bound once every entity of the namespace is known, so
+/// entities can be referenced before they are declared and may reference each other. The
+/// namespace owns its entities, a reference only holds a weak pointer to avoid reference cycles.
+#[derive(Clone)]
+pub struct Reference {
+    identifier: Identifier,
+    entity: RefCell<Weak<Entity>>,
+    /// The type arguments of a reference to a generic structure, e.g. `Order` of `Page<Order>`.
+    arguments: Vec<Reference>,
+    span: Span,
+}
+
+impl Reference {
+    pub fn new(identifier: Identifier, span: Span) -> Reference {
+        Reference { identifier, entity: RefCell::new(Weak::new()), arguments: vec![], span }
+    }
+
+    pub fn with_arguments(mut self, arguments: Vec<Reference>) -> Reference {
+        self.arguments = arguments;
+        self
+    }
+
+    pub fn arguments(&self) -> &[Reference] { &self.arguments }
+
+    /// Replaces the references to the type `parameters` by the corresponding `arguments`, even
+    /// when they are nested in type arguments.
+    pub fn substitute(&self, parameters: &[Rc<Entity>], arguments: &[Reference]) -> Reference {
+        let position = self.get().and_then(|e| parameters.iter().position(|p| Rc::ptr_eq(p, &e)));
+        match position.and_then(|index| arguments.get(index)) {
+            Some(argument) => argument.clone(),
+            None => {
+                let mut substituted = self.clone();
+                substituted.arguments =
+                    self.arguments.iter().map(|a| a.substitute(parameters, arguments)).collect();
+                substituted
+            },
+        }
+    }
+
+    /// Returns where the reference is written in the source.
+    pub fn span(&self) -> Span { self.span }
+
+    pub fn bind(&self, entity: &Rc<Entity>) { self.entity.replace(Rc::downgrade(entity)); }
+
+    pub fn is_bound(&self) -> bool { self.get().is_some() }
+
+    pub fn get(&self) -> Option<Rc<Entity>> { self.entity.borrow().upgrade() }
+
+    /// Returns the referenced entity.
+    ///
+    /// Panics if the reference has not been bound or the namespace owning the entity is gone.
+    pub fn entity(&self) -> Rc<Entity> {
+        self.get().unwrap_or_else(|| panic!("Reference to `{}` is not bound.", self.identifier))
+    }
+}
+
+impl Identifying for Reference {
+    fn identifier(&self) -> String { self.identifier.clone() }
+}
+
+impl fmt::Debug for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "Reference({})", self) }
+}
+
+/// Writes the reference as in the model, e.g. `Page<Order>`.
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.identifier)?;
+        if !self.arguments.is_empty() {
+            let arguments: Vec<String> = self.arguments.iter().map(|a| a.to_string()).collect();
+            write!(f, "<{}>", arguments.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Entity {
     Structure(Structure),
     Scalar(Scalar),
+    CustomScalar(CustomScalar),
     Enum(Enum),
+    Union(Union),
+    Service(Service),
+    TypeParameter(TypeParameter),
+    /// The built-in `Map<K, V>`, associating keys with values.
+    Map,
+}
+
+/// A placeholder for a type of a generic structure, e.g. `T` of `Page<T>`.
+#[derive(Debug, Clone)]
+pub struct TypeParameter {
+    pub name: String,
+    pub span: Span,
+}
+
+impl Identifying for TypeParameter {
+    fn identifier(&self) -> String { self.name.clone() }
+}
+
+impl Entity {
+    /// Returns where the entity is declared. Scalars and maps are built in and have no location.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Entity::Structure(s) => Some(s.span),
+            Entity::Enum(e) => Some(e.span),
+            Entity::CustomScalar(c) => Some(c.span),
+            Entity::Union(u) => Some(u.span),
+            Entity::Service(s) => Some(s.span),
+            Entity::TypeParameter(p) => Some(p.span),
+            Entity::Scalar(_) | Entity::Map => None,
+        }
+    }
+
+    /// Returns every reference to another entity used by the entity.
+    pub fn references(&self) -> Vec<&Reference> {
+        match self {
+            Entity::Structure(struc) => struc.references(),
+            Entity::CustomScalar(custom) => vec![&custom.base],
+            Entity::Union(union) => union.members.iter().collect(),
+            Entity::Service(service) => service.references(),
+            Entity::Scalar(_) | Entity::Enum(_) | Entity::TypeParameter(_) | Entity::Map => vec![],
+        }
+    }
+
+    /// The built-in scalar the values of the entity belong to, following custom scalars to the
+    /// scalar they are based on.
+    pub fn scalar(&self) -> Option<Scalar> {
+        match self {
+            Entity::Scalar(scalar) => Some(*scalar),
+            Entity::CustomScalar(custom) => match custom.base.get()?.as_ref() {
+                Entity::Scalar(scalar) => Some(*scalar),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 }
 
 impl Identifying for Entity {
@@ -79,7 +586,12 @@ impl Identifying for Entity {
         match self {
             Entity::Structure(s) => s.identifier(),
             Entity::Scalar(s) => s.identifier(),
+            Entity::CustomScalar(c) => c.identifier(),
             Entity::Enum(e) => e.identifier(),
+            Entity::Union(u) => u.identifier(),
+            Entity::Service(s) => s.identifier(),
+            Entity::TypeParameter(p) => p.identifier(),
+            Entity::Map => String::from("Map"),
         }
     }
 }
@@ -92,6 +604,61 @@ pub enum Multiplicity {
     Optional,
 }
 
+impl Multiplicity {
+    /// The least and the most number of values allowed, `None` being unbounded.
+    pub fn bounds(&self) -> (usize, Option<usize>) {
+        let lower = |n: &Number| match n {
+            Number::Discrete(n) => *n,
+            Number::NegativeInfinity => 0,
+            Number::Infinity => usize::MAX,
+        };
+        let upper = |n: &Number| match n {
+            Number::Discrete(n) => Some(*n),
+            Number::NegativeInfinity => Some(0),
+            Number::Infinity => None,
+        };
+        match self {
+            Multiplicity::Single => (1, Some(1)),
+            Multiplicity::Optional => (0, Some(1)),
+            Multiplicity::Concrete(n) => (lower(n), upper(n)),
+            Multiplicity::UnderUpper(l, u) => (lower(l), upper(u)),
+        }
+    }
+
+    /// Whether the multiplicity is written in brackets, which makes a list even of a single value.
+    pub fn is_list(&self) -> bool {
+        matches!(self, Multiplicity::Concrete(_) | Multiplicity::UnderUpper(..))
+    }
+
+    /// Whether every number of values allowed by this multiplicity is allowed by `other` as
+    /// well, e.g. `[2, 4]` narrows `[+]` and a single value narrows an optional one. Lists only
+    /// narrow lists.
+    pub fn narrows(&self, other: &Multiplicity) -> bool {
+        let (lower, upper) = self.bounds();
+        let (other_lower, other_upper) = other.bounds();
+        let within = match (upper, other_upper) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(upper), Some(other_upper)) => upper <= other_upper,
+        };
+        self.is_list() == other.is_list() && lower >= other_lower && within
+    }
+}
+
+impl fmt::Display for Multiplicity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            // Written without a multiplicity, `[1]` would be a list of one value.
+            Multiplicity::Single => write!(f, "exactly one"),
+            Multiplicity::Optional => write!(f, "?"),
+            Multiplicity::Concrete(n) => write!(f, "[{}]", n),
+            Multiplicity::UnderUpper(Number::Discrete(1), Number::Infinity) => write!(f, "[+]"),
+            Multiplicity::UnderUpper(Number::Discrete(0), Number::Infinity) => write!(f, "[*]"),
+            Multiplicity::UnderUpper(lower, upper) => write!(f, "[{}, {}]", lower, upper),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Number {
     Discrete(usize),
@@ -99,22 +666,329 @@ pub enum Number {
     NegativeInfinity,
 }
 
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Number::Discrete(n) => write!(f, "{}", n),
+            Number::Infinity => write!(f, "*"),
+            Number::NegativeInfinity => write!(f, "-*"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Attribute {
     pub name: String,
-    pub entity: Rc<Entity>,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub entity: Reference,
     pub multiplicity: Multiplicity,
+    /// Whether the attribute identifies the instances of its structure, alone or along with the
+    /// other keys of the structure.
+    pub key: Option<Key>,
+    /// Whether an attribute referencing structures owns the instances it references.
+    pub ownership: Ownership,
+    /// The attribute of the referenced structure which is the other end of the relationship,
+    /// e.g. `customer` of `orders: Order [*] inverse customer`.
+    pub inverse: Option<Inverse>,
+    /// Restrictions of the values of the attribute, e.g. `(min 0, max 150)`.
+    pub constraints: Vec<Constraint>,
+    /// The value the attribute starts with, e.g. `true` of `active: Boolean = true`.
+    pub default: Option<Value>,
+    pub span: Span,
+}
+
+/// How the value of a key attribute comes about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// `key`, a value given when the instance is created.
+    Provided,
+    /// `generated key`, a value assigned when the instance is stored, e.g. a sequential number.
+    Generated,
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Key::Provided => write!(f, "key"),
+            Key::Generated => write!(f, "generated key"),
+        }
+    }
+}
+
+/// What an attribute referencing structures means for the instances it references.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Ownership {
+    /// The instances exist on their own, e.g. the `customer` of an order.
+    #[default]
+    Association,
+    /// `contains`, the instances are parts of the instance referencing them and are deleted
+    /// along with it, e.g. the `lines` of an order.
+    Composition,
+}
+
+/// The name of the attribute at the other end of a relationship.
+#[derive(Debug, Clone)]
+pub struct Inverse {
+    pub name: String,
+    pub span: Span,
+}
+
+/// How many instances each end of a relationship relates to, the end declaring the attribute
+/// coming first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cardinality {
+    OneToOne,
+    OneToMany,
+    ManyToOne,
+    ManyToMany,
+}
+
+impl Attribute {
+    /// The structure the attribute references, if it is a relationship.
+    pub fn target(&self) -> Option<Rc<Entity>> {
+        self.entity.get().filter(|e| matches!(e.as_ref(), Entity::Structure(_)))
+    }
+
+    /// The attribute at the other end of the relationship, which is named by this attribute or
+    /// names this attribute as its inverse.
+    pub fn inverse_attribute(&self, owner: &Structure) -> Option<Attribute> {
+        let target = self.target()?;
+        let target = match target.as_ref() {
+            Entity::Structure(target) => target,
+            _ => return None,
+        };
+        let attributes = target.effective_attributes();
+        if let Some(inverse) = &self.inverse {
+            return attributes.into_iter().find(|a| a.name == inverse.name);
+        }
+        let mut owners: Vec<String> = owner.ancestors().iter().map(|a| a.identifier()).collect();
+        owners.push(owner.name.clone());
+        attributes.into_iter().find(|a| {
+            let names = matches!(&a.inverse, Some(inverse) if inverse.name == self.name);
+            names && matches!(a.target(), Some(e) if owners.contains(&e.identifier()))
+        })
+    }
+
+    /// How many instances each end of the relationship `owner` declares relates to. Without an
+    /// inverse, the parts of a composition belong to one instance of `owner` and the instances
+    /// of an association may be referenced by many.
+    pub fn cardinality(&self, owner: &Structure) -> Option<Cardinality> {
+        self.target()?;
+        let many = |attr: &Attribute| attr.multiplicity.bounds().1 != Some(1);
+        let inverse_many = match self.inverse_attribute(owner) {
+            Some(inverse) => many(&inverse),
+            None => self.ownership == Ownership::Association,
+        };
+        let cardinality = match (inverse_many, many(self)) {
+            (false, false) => Cardinality::OneToOne,
+            (false, true) => Cardinality::OneToMany,
+            (true, false) => Cardinality::ManyToOne,
+            (true, true) => Cardinality::ManyToMany,
+        };
+        Some(cardinality)
+    }
+}
+
+/// A restriction of the values an attribute accepts, e.g. `min 0` or `pattern "[a-z]+"`. Each
+/// value of a list has to satisfy it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+    pub kind: ConstraintKind,
+    pub value: Value,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintKind {
+    /// The smallest number allowed.
+    Min,
+    /// The greatest number allowed.
+    Max,
+    /// The least number of characters of a string.
+    MinLength,
+    /// The most number of characters of a string.
+    MaxLength,
+    /// A regular expression strings have to match.
+    Pattern,
+}
+
+impl fmt::Display for ConstraintKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ConstraintKind::Min => "min",
+            ConstraintKind::Max => "max",
+            ConstraintKind::MinLength => "minLength",
+            ConstraintKind::MaxLength => "maxLength",
+            ConstraintKind::Pattern => "pattern",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.kind, self.value)
+    }
+}
+
+/// A literal value written in the model.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Double(f64),
+    Boolean(bool),
+    /// The name of a member of an enum.
+    Member(String),
+    /// `[]`, a list without items.
+    EmptyList,
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::String(string) => write!(f, "\"{}\"", string),
+            Value::Integer(integer) => write!(f, "{}", integer),
+            // Written without an exponent, which the grammar does not accept, and with the
+            // fraction of whole numbers, e.g. `1.0`.
+            Value::Double(double) if double.fract() == 0.0 => write!(f, "{}.0", double),
+            Value::Double(double) => write!(f, "{}", double),
+            Value::Boolean(boolean) => write!(f, "{}", boolean),
+            Value::Member(member) => write!(f, "{}", member),
+            Value::EmptyList => write!(f, "[]"),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Parameter {
     pub name: String,
-    pub entity: Rc<Entity>,
+    pub entity: Reference,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct Operation {
     pub name: String,
-    pub returns: Option<Rc<Entity>>,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub returns: Option<Reference>,
     pub parameter: Vec<Parameter>,
+    /// Whether the operation is marked as a `query` or a `command`. Unmarked operations of
+    /// services are commands.
+    pub kind: Option<OperationKind>,
+    /// The events the operation emits, e.g. `OrderPlaced` of
+    /// `place(order: Order) emits OrderPlaced`.
+    pub emits: Vec<Reference>,
+    pub span: Span,
+}
+
+/// Whether an operation only reads the model or changes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationKind {
+    /// `query`, an operation without side effects.
+    Query,
+    /// `command`, an operation changing the model.
+    Command,
+}
+
+impl fmt::Display for OperationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OperationKind::Query => write!(f, "query"),
+            OperationKind::Command => write!(f, "command"),
+        }
+    }
+}
+
+impl Operation {
+    pub fn is_query(&self) -> bool { self.kind == Some(OperationKind::Query) }
+
+    /// Returns the types of the parameters, the return type and the events of the operation.
+    pub fn references(&self) -> Vec<&Reference> {
+        let mut references: Vec<&Reference> = self.parameter.iter().map(|p| &p.entity).collect();
+        references.extend(self.returns.iter());
+        references.extend(self.emits.iter());
+        references
+    }
+
+    /// The name and the parameter types of the operation, e.g. `rent(Integer, Date)`.
+    pub fn signature(&self) -> String {
+        let types: Vec<String> = self.parameter.iter().map(|p| p.entity.identifier()).collect();
+        format!("{}({})", self.name, types.join(", "))
+    }
+}
+
+/// Metadata attached to an element of the model, like `@id`, `@deprecated("use x")` or hints
+/// for a single target like `@graphql(skip)`.
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    pub name: String,
+    pub value: Option<String>,
+    pub span: Span,
+}
+
+/// An element of the model which can be annotated.
+///
+/// Annotations are written in front of the declaration, after its `///` comments, which every
+/// annotated element keeps in its `docs` field.
+pub trait Annotated {
+    fn annotations(&self) -> &[Annotation];
+
+    fn annotation(&self, name: &str) -> Option<&Annotation> {
+        self.annotations().iter().find(|a| a.name == name)
+    }
+
+    fn is_annotated(&self, name: &str) -> bool { self.annotation(name).is_some() }
+
+    /// Whether the element carries the hint `@<target>(<value>)`, e.g. `@graphql(skip)`.
+    fn has_hint(&self, target: &str, value: &str) -> bool {
+        self.annotations().iter().any(|a| a.name == target && a.value.as_deref() == Some(value))
+    }
+}
+
+impl Annotated for Structure {
+    fn annotations(&self) -> &[Annotation] { &self.annotations }
+}
+
+impl Annotated for CustomScalar {
+    fn annotations(&self) -> &[Annotation] { &self.annotations }
+}
+
+impl Annotated for Service {
+    fn annotations(&self) -> &[Annotation] { &self.annotations }
+}
+
+impl Annotated for Union {
+    fn annotations(&self) -> &[Annotation] { &self.annotations }
+}
+
+impl Annotated for Enum {
+    fn annotations(&self) -> &[Annotation] { &self.annotations }
+}
+
+impl Annotated for EnumMember {
+    fn annotations(&self) -> &[Annotation] { &self.annotations }
+}
+
+impl Annotated for Attribute {
+    fn annotations(&self) -> &[Annotation] { &self.annotations }
+}
+
+impl Annotated for Operation {
+    fn annotations(&self) -> &[Annotation] { &self.annotations }
+}
+
+impl Annotated for Entity {
+    fn annotations(&self) -> &[Annotation] {
+        match self {
+            Entity::Structure(s) => s.annotations(),
+            Entity::Enum(e) => e.annotations(),
+            Entity::CustomScalar(c) => c.annotations(),
+            Entity::Union(u) => u.annotations(),
+            Entity::Service(s) => s.annotations(),
+            Entity::Scalar(_) | Entity::TypeParameter(_) | Entity::Map => &[],
+        }
+    }
 }
@@ -0,0 +1,477 @@
+use ast::{split_qualified, Entity, Identifying, Namespace, Package, Reference, Span, Structure};
+use lsp_types::{CompletionItem, CompletionItemKind};
+use parser::check_source;
+use parser::diagnostic::Diagnostics;
+use parser::format::{print_attribute, print_entity, print_operation};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// The result of parsing a document along with the files it imports. Locations are byte
+/// offsets of the files.
+pub struct Analysis {
+    pub file: PathBuf,
+    /// Missing if the model could not be resolved.
+    pub package: Option<Package>,
+    pub diagnostics: Diagnostics,
+}
+
+impl Analysis {
+    pub fn new(code: &str, file: &Path) -> Analysis {
+        let (package, diagnostics) = check_source(code, file);
+        Analysis { file: file.to_path_buf(), package, diagnostics }
+    }
+
+    /// The source of `file`, which is the analysed document or one of its imports.
+    pub fn source(&self, file: &Path) -> Option<&str> { self.diagnostics.source(Some(file)) }
+
+    /// Finds the entity named at `offset` of the document, either by a reference or by its
+    /// declaration.
+    pub fn symbol_at(&self, offset: usize) -> Option<Rc<Entity>> {
+        let package = self.package.as_ref()?;
+        for namespace in self.namespaces() {
+            let source = self.source(self.file.as_path()).unwrap_or("");
+            for entity in namespace.entities.iter() {
+                let name = declaration_name(entity, source);
+                if matches!(name, Some(s) if contains(&s, offset)) {
+                    return namespace.find_entity(entity.identifier());
+                }
+                let reference =
+                    entity.references().into_iter().find(|r| contains(&r.span(), offset));
+                if let Some(reference) = reference {
+                    return lookup(package, namespace, reference);
+                }
+            }
+        }
+        None
+    }
+
+    /// The file and the name of the declaration of `entity`. Built-in scalars and maps are not
+    /// declared.
+    pub fn declaration(&self, entity: &Rc<Entity>) -> Option<(PathBuf, Span)> {
+        let package = self.package.as_ref()?;
+        let namespace = owner(package, entity)?;
+        let file = namespace.source.clone()?;
+        let span = declaration_name(entity, self.source(file.as_path()).unwrap_or(""))?;
+        Some((file, span))
+    }
+
+    /// Describes the entity at `offset` in markdown, including the features it inherits.
+    pub fn hover(&self, offset: usize) -> Option<String> {
+        let entity = self.symbol_at(offset)?;
+        let mut hover = format!("```pakken\n{}```", print_entity(&entity));
+        match entity.as_ref() {
+            Entity::Structure(struc) => hover += inherited(struc).as_str(),
+            Entity::Scalar(_) => hover += "\nbuilt-in scalar",
+            Entity::TypeParameter(_) => hover += "\ntype parameter",
+            Entity::Map => hover += "\nbuilt-in map of keys to values",
+            Entity::Enum(_) | Entity::CustomScalar(_) | Entity::Union(_) | Entity::Service(_) => (),
+        }
+        Some(hover)
+    }
+
+    /// All entities which can be referenced from the document. Entities of other files are
+    /// qualified with their namespace.
+    pub fn completions(&self) -> Vec<CompletionItem> {
+        let package = match &self.package {
+            Some(package) => package,
+            None => return vec![],
+        };
+        let mut items: Vec<CompletionItem> = vec![];
+        for namespace in &package.namespaces {
+            let local = namespace.source.as_deref() == Some(self.file.as_path());
+            for entity in &namespace.entities {
+                let (label, kind) = match entity.as_ref() {
+                    Entity::Scalar(scalar) => (scalar.to_string(), CompletionItemKind::KEYWORD),
+                    Entity::Map => (String::from("Map"), CompletionItemKind::KEYWORD),
+                    Entity::Structure(struc) if local => (struc.name.clone(), kind(struc)),
+                    Entity::Enum(enumeration) if local => {
+                        (enumeration.identifier.clone(), CompletionItemKind::ENUM)
+                    },
+                    Entity::CustomScalar(custom) if local => {
+                        (custom.name.clone(), CompletionItemKind::TYPE_PARAMETER)
+                    },
+                    Entity::Union(union) if local => {
+                        (union.name.clone(), CompletionItemKind::STRUCT)
+                    },
+                    Entity::Structure(struc) => {
+                        (format!("{}.{}", namespace.identifier, struc.name), kind(struc))
+                    },
+                    Entity::Enum(enumeration) => (
+                        format!("{}.{}", namespace.identifier, enumeration.identifier),
+                        CompletionItemKind::ENUM,
+                    ),
+                    Entity::CustomScalar(custom) => (
+                        format!("{}.{}", namespace.identifier, custom.name),
+                        CompletionItemKind::TYPE_PARAMETER,
+                    ),
+                    Entity::Union(union) => (
+                        format!("{}.{}", namespace.identifier, union.name),
+                        CompletionItemKind::STRUCT,
+                    ),
+                    // Type parameters are only declared by structures, services are no types.
+                    Entity::TypeParameter(_) | Entity::Service(_) => continue,
+                };
+                if items.iter().all(|i| i.label != label) {
+                    // The head of the declaration, e.g. `Bike : Vehicle`.
+                    let printed = print_entity(entity);
+                    let head = printed.lines().next().unwrap_or("");
+                    let detail =
+                        Some(head.trim_end_matches(" { }").trim_end_matches(" {").to_owned());
+                    items.push(CompletionItem {
+                        label,
+                        kind: Some(kind),
+                        detail,
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+        items
+    }
+
+    /// The places to replace with `new_name` to rename the entity at `offset`: its declaration
+    /// and every reference to it within the model.
+    pub fn rename(&self, offset: usize, new_name: &str) -> Result<Vec<(PathBuf, Span)>, String> {
+        let valid = new_name.starts_with(|c: char| c.is_ascii_uppercase())
+            && new_name.chars().all(|c| c.is_ascii_alphanumeric());
+        if !valid {
+            return Err(format!("`{}` is not a valid entity name", new_name));
+        }
+        let entity = self.symbol_at(offset).ok_or("There is no entity to rename")?;
+        let declaration = self.declaration(&entity).ok_or("Built-in entities cannot be renamed")?;
+        let package = self.package.as_ref().ok_or("The model contains errors")?;
+
+        let mut edits = vec![declaration];
+        edits.extend(references(package.namespaces.iter(), &entity));
+        Ok(edits)
+    }
+
+    /// The namespace and the name of the entity at `offset`, if other files importing its
+    /// namespace can reference it, which type parameters and built-in entities cannot.
+    pub fn exported(&self, offset: usize) -> Option<(String, String)> {
+        let entity = self.symbol_at(offset)?;
+        let namespace = owner(self.package.as_ref()?, &entity)?;
+        match entity.as_ref() {
+            Entity::TypeParameter(_) | Entity::Scalar(_) | Entity::Map => None,
+            _ => Some((namespace.identifier.clone(), entity.identifier())),
+        }
+    }
+
+    /// The places in the document itself referencing the entity `name` of `namespace`, which
+    /// another file declares.
+    pub fn references_to(&self, namespace: &str, name: &str) -> Vec<(PathBuf, Span)> {
+        let package = match &self.package {
+            Some(package) => package,
+            None => return vec![],
+        };
+        match package.find_namespace(namespace).and_then(|n| n.find_entity(name.to_owned())) {
+            Some(entity) => references(self.namespaces(), &entity),
+            None => vec![],
+        }
+    }
+
+    /// The namespaces declared in the document itself.
+    fn namespaces(&self) -> impl Iterator<Item = &Namespace> {
+        let file = self.file.as_path();
+        let namespaces = self.package.iter().flat_map(|p| p.namespaces.iter());
+        namespaces.filter(move |n| n.source.as_deref() == Some(file))
+    }
+}
+
+fn kind(struc: &Structure) -> CompletionItemKind {
+    if struc.is_interface() {
+        CompletionItemKind::INTERFACE
+    } else if struc.is_event() {
+        CompletionItemKind::EVENT
+    } else {
+        CompletionItemKind::CLASS
+    }
+}
+
+fn contains(span: &Span, offset: usize) -> bool { span.start <= offset && offset <= span.end }
+
+/// The span of the name an entity is declared with in `source`, which is where its declaration
+/// starts unless it starts with a keyword like `scalar`, `union` or `interface`.
+fn declaration_name(entity: &Entity, source: &str) -> Option<Span> {
+    let span = entity.span()?;
+    let (name, keyword) = match entity {
+        Entity::Structure(struc) => (struc.name.as_str(), struc.kind.to_string().len()),
+        Entity::Enum(enumeration) => (enumeration.identifier.as_str(), 0),
+        Entity::CustomScalar(custom) => (custom.name.as_str(), "scalar".len()),
+        Entity::Union(union) => (union.name.as_str(), "union".len()),
+        Entity::Service(service) => (service.name.as_str(), "service".len()),
+        Entity::TypeParameter(parameter) => return Some(parameter.span),
+        Entity::Scalar(_) | Entity::Map => return None,
+    };
+    let start = match keyword {
+        0 => span.start,
+        _ => span.start + keyword + source.get(span.start + keyword ..)?.find(name)?,
+    };
+    let column = span.column + (start - span.start);
+    Some(Span { start, end: start + name.len(), column, ..span })
+}
+
+/// Looks up the entity `reference` of `namespace` refers to.
+fn lookup(package: &Package, namespace: &Namespace, reference: &Reference) -> Option<Rc<Entity>> {
+    let identifier = reference.identifier();
+    match split_qualified(identifier.as_str()) {
+        Some((qualifier, name)) => package.find_namespace(qualifier)?.find_entity(name.to_owned()),
+        None => namespace.find_entity(identifier),
+    }
+    .or_else(|| reference.get())
+}
+
+/// The namespace declaring `entity`, which for a type parameter is the one of its structure.
+fn owner<'a>(package: &'a Package, entity: &Rc<Entity>) -> Option<&'a Namespace> {
+    let declares = |declared: &Rc<Entity>| {
+        Rc::ptr_eq(declared, entity)
+            || matches!(declared.as_ref(), Entity::Structure(s) if s.parameters.iter().any(|p| Rc::ptr_eq(p, entity)))
+    };
+    package.namespaces.iter().find(|n| n.entities.iter().any(declares))
+}
+
+/// The places of the references to `entity` within `namespaces`.
+fn references<'a>(
+    namespaces: impl Iterator<Item = &'a Namespace>, entity: &Rc<Entity>,
+) -> Vec<(PathBuf, Span)> {
+    let mut places = vec![];
+    for namespace in namespaces {
+        let file = match &namespace.source {
+            Some(file) => file,
+            None => continue,
+        };
+        for declared in &namespace.entities {
+            for reference in declared.references() {
+                if matches!(reference.get(), Some(e) if Rc::ptr_eq(&e, entity)) {
+                    places.push((file.clone(), unqualified(reference)));
+                }
+            }
+        }
+    }
+    places
+}
+
+/// The part of `reference` naming the entity, leaving out its namespace.
+fn unqualified(reference: &Reference) -> Span {
+    let span = reference.span();
+    let identifier = reference.identifier();
+    let name = split_qualified(identifier.as_str()).map_or(identifier.as_str(), |(_, name)| name);
+    Span { start: span.end - name.len(), ..span }
+}
+
+/// Lists the features `struc` inherits, grouped by the structure declaring them. Attributes
+/// redeclared further down are left out.
+fn inherited(struc: &Structure) -> String {
+    let mut description = String::new();
+    let mut declared: Vec<String> = struc.attributes.iter().map(|a| a.name.clone()).collect();
+    let mut visited: Vec<Rc<Entity>> = vec![];
+    let mut current = struc.parent.as_ref().and_then(Reference::get);
+    while let Some(parent) = current {
+        if visited.iter().any(|v| Rc::ptr_eq(v, &parent)) {
+            break;
+        }
+        visited.push(Rc::clone(&parent));
+        let parent_struc = match parent.as_ref() {
+            Entity::Structure(parent_struc) => parent_struc,
+            _ => break,
+        };
+        let mut features = vec![];
+        for attr in &parent_struc.attributes {
+            if !declared.contains(&attr.name) {
+                declared.push(attr.name.clone());
+                features.push(print_attribute(attr));
+            }
+        }
+        features.extend(parent_struc.operations.iter().map(print_operation));
+        if !features.is_empty() {
+            description += format!(
+                "\n\ninherited from `{}`:\n```pakken\n{}\n```",
+                parent_struc.name,
+                features.join("\n")
+            )
+            .as_str();
+        }
+        current = parent_struc.parent.as_ref().and_then(Reference::get);
+    }
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::Analysis;
+    use ast::Identifying;
+    use lsp_types::CompletionItemKind;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::rc::Rc;
+
+    const CODE: &str = "org.mobile {\n    Vehicle {\n        name: String,\n        tires: Tire \
+                        [+],\n        countTires(): Integer\n    }\n    Tire {\n        size: \
+                        Integer\n    }\n    Bike : Vehicle {\n        tires: Tire [2, 4]\n    \
+                        }\n}\n";
+
+    fn analyse(code: &str) -> Analysis { Analysis::new(code, Path::new("model.pakken")) }
+
+    #[test]
+    fn test_definition() {
+        let analysis = analyse(CODE);
+        let offset = CODE.find("Tire [+]").unwrap() + 1;
+        let entity = analysis.symbol_at(offset).expect("Should find the reference");
+        assert_eq!(entity.identifier(), "Tire");
+        let (file, span) = analysis.declaration(&entity).expect("Should be declared");
+        assert_eq!(file, PathBuf::from("model.pakken"));
+        assert_eq!(&CODE[span.start .. span.end], "Tire");
+        assert_eq!(span.start, CODE.find("Tire {").unwrap());
+    }
+
+    #[test]
+    fn test_hover() {
+        let analysis = analyse(CODE);
+        let hover = analysis.hover(CODE.find("Bike").unwrap()).expect("Should describe Bike");
+        let expected = [
+            "```pakken",
+            "Bike : Vehicle {",
+            "    tires: Tire [2, 4]",
+            "}",
+            "```",
+            "",
+            "inherited from `Vehicle`:",
+            "```pakken",
+            "name: String",
+            "countTires(): Integer",
+            "```",
+        ];
+        assert_eq!(hover, expected.join("\n"));
+        assert!(analysis.hover(CODE.find("String").unwrap()).unwrap().contains("built-in"));
+    }
+
+    #[test]
+    fn test_completions() {
+        let analysis = analyse(CODE);
+        let labels: Vec<String> = analysis.completions().into_iter().map(|i| i.label).collect();
+        for label in &["Vehicle", "Tire", "Bike", "String", "Integer"] {
+            assert!(labels.contains(&label.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_rename() {
+        let analysis = analyse(CODE);
+        let edits = analysis.rename(CODE.find("Tire {").unwrap(), "Wheel").expect("Should rename");
+        assert_eq!(edits.len(), 3);
+        assert!(edits.iter().all(|(_, span)| &CODE[span.start .. span.end] == "Tire"));
+        assert!(analysis.rename(CODE.find("Tire {").unwrap(), "wheel").is_err());
+        assert!(analysis.rename(CODE.find("String").unwrap(), "Text").is_err());
+    }
+
+    #[test]
+    fn test_rename_qualified() {
+        let code = "org.a {\n    A { b: org.b.B }\n}\norg.b {\n    B { c: String }\n}\n";
+        let analysis = analyse(code);
+        let edits = analysis.rename(code.find("org.b.B").unwrap(), "C").expect("Should rename");
+        let renamed: Vec<&str> = edits.iter().map(|(_, s)| &code[s.start .. s.end]).collect();
+        assert_eq!(renamed, vec!["B", "B"]);
+    }
+
+    #[test]
+    fn test_references_to() {
+        let path = Path::new("../parser/test/billing.pakken");
+        let code = fs::read_to_string(path).unwrap();
+        let billing = Analysis::new(code.as_str(), path);
+        let exported = billing.exported(code.find("Invoice").unwrap());
+        assert_eq!(exported, Some((String::from("org.billing"), String::from("Invoice"))));
+        assert_eq!(billing.exported(code.find("Double").unwrap()), None);
+
+        let path = Path::new("../parser/test/shop.pakken");
+        let code = fs::read_to_string(path).unwrap();
+        let shop = Analysis::new(code.as_str(), path);
+        let edits = shop.references_to("org.billing", "Invoice");
+        let renamed: Vec<&str> = edits.iter().map(|(_, s)| &code[s.start .. s.end]).collect();
+        assert_eq!(renamed, vec!["Invoice"]);
+        assert!(edits.iter().all(|(file, _)| file == path));
+    }
+
+    #[test]
+    fn test_custom_scalar() {
+        let code =
+            "org.a {\n    /// An address.\n    scalar   Email : String\n    A { b: Email }\n}\n";
+        let analysis = analyse(code);
+        let entity = analysis.symbol_at(code.find("Email }").unwrap()).expect("Should find Email");
+        let (_, span) = analysis.declaration(&entity).expect("Should be declared");
+        assert_eq!(&code[span.start .. span.end], "Email");
+        assert_eq!(span.column, 14);
+        let hover = analysis.hover(span.start).expect("Should describe Email");
+        assert!(hover.contains("/// An address.\nscalar Email : String"));
+    }
+
+    #[test]
+    fn test_interface() {
+        let code = "org.a {\n    interface Named { name: String }\n    A implements Named { name: \
+                    String }\n}\n";
+        let analysis = analyse(code);
+        let declared = analysis.symbol_at(code.find("Named").unwrap()).expect("Should find Named");
+        let entity = analysis.symbol_at(code.rfind("Named").unwrap()).expect("Should find Named");
+        assert!(Rc::ptr_eq(&declared, &entity));
+        let (_, span) = analysis.declaration(&entity).expect("Should be declared");
+        assert_eq!(&code[span.start .. span.end], "Named");
+        assert_eq!(span.column, 15);
+        let completions = analysis.completions();
+        let named = completions.iter().find(|c| c.label == "Named").expect("Should complete Named");
+        assert_eq!(named.kind, Some(CompletionItemKind::INTERFACE));
+        assert_eq!(named.detail.as_deref(), Some("interface Named"));
+    }
+
+    #[test]
+    fn test_type_parameter() {
+        let code = "org.a {\n    Page<T> { items: T [*] }\n    A { b: Page<A> }\n}\n";
+        let analysis = analyse(code);
+        let entity = analysis.symbol_at(code.find("T [*]").unwrap()).expect("Should find T");
+        let (_, span) = analysis.declaration(&entity).expect("Should be declared");
+        assert_eq!(span.start, code.find("T>").unwrap());
+        let hover = analysis.hover(code.find("T [*]").unwrap()).expect("Should describe T");
+        assert!(hover.ends_with("type parameter"));
+        let argument = analysis.symbol_at(code.find("A>").unwrap()).expect("Should find A");
+        assert_eq!(argument.identifier(), "A");
+    }
+
+    #[test]
+    fn test_union() {
+        let code = "org.a {\n    union Found = A | B\n    A { a: String }\n    B { c: Found }\n}\n";
+        let analysis = analyse(code);
+        let entity = analysis.symbol_at(code.find("A |").unwrap()).expect("Should find A");
+        assert_eq!(entity.identifier(), "A");
+        let edits = analysis.rename(code.find("A {").unwrap(), "C").expect("Should rename");
+        assert_eq!(edits.len(), 2);
+        let found = analysis.symbol_at(code.find("Found }").unwrap()).expect("Should find Found");
+        let (_, span) = analysis.declaration(&found).expect("Should be declared");
+        assert_eq!(span.start, code.find("Found =").unwrap());
+    }
+
+    #[test]
+    fn test_service() {
+        let code = "org.a {\n    /// Orders.\n    service S {\n        query find(a: A): A\n    \
+                    }\n    A { a: String }\n}\n";
+        let analysis = analyse(code);
+        let entity = analysis.symbol_at(code.find("A)").unwrap()).expect("Should find A");
+        assert_eq!(entity.identifier(), "A");
+        let edits = analysis.rename(code.find("A {").unwrap(), "B").expect("Should rename");
+        assert_eq!(edits.len(), 3);
+        let hover = analysis.hover(code.find("S {").unwrap()).expect("Should hover S");
+        assert!(hover.contains("Orders."));
+        assert!(!analysis.completions().iter().any(|c| c.label == "S"));
+    }
+
+    #[test]
+    fn test_event() {
+        let code =
+            "org.a {\n    event Placed { a: String }\n    service S { place() emits Placed }\n}\n";
+        let analysis = analyse(code);
+        let entity = analysis.symbol_at(code.find("Placed }").unwrap()).expect("Should find it");
+        assert_eq!(entity.identifier(), "Placed");
+        let edits = analysis.rename(code.find("Placed {").unwrap(), "Sent").expect("Should rename");
+        assert_eq!(edits.len(), 2);
+        let completions = analysis.completions();
+        let placed = completions.iter().find(|c| c.label == "Placed").expect("Should complete");
+        assert_eq!(placed.kind, Some(CompletionItemKind::EVENT));
+    }
+}
@@ -0,0 +1,5 @@
+mod analysis;
+mod position;
+mod server;
+
+pub use server::run;
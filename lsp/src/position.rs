@@ -0,0 +1,61 @@
+use ast::Span;
+use lsp_types::{Position, Range};
+
+/// Converts `position`, whose character counts UTF-16 code units as the protocol demands, into
+/// a byte offset of `text`. Positions beyond a line or the text are clamped.
+pub fn offset(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0 .. position.line {
+        match text[line_start ..].find('\n') {
+            Some(end) => line_start += end + 1,
+            None => return text.len(),
+        }
+    }
+    let line = text[line_start ..].split('\n').next().unwrap_or("");
+    let mut units = 0;
+    for (index, c) in line.char_indices() {
+        if units >= position.character as usize {
+            return line_start + index;
+        }
+        units += c.len_utf16();
+    }
+    line_start + line.len()
+}
+
+/// Converts the byte `offset` of `text` into a position of the protocol.
+pub fn position(text: &str, offset: usize) -> Position {
+    let before = &text[.. offset.min(text.len())];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let character: usize = before[line_start ..].chars().map(char::len_utf16).sum();
+    Position::new(line as u32, character as u32)
+}
+
+pub fn range(text: &str, span: Span) -> Range {
+    Range::new(position(text, span.start), position(text, span.end))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::position::{offset, position};
+    use lsp_types::Position;
+
+    #[test]
+    fn test_round_trip() {
+        let text = "org.a {\n    A { b: String }\n}";
+        let b = text.find('b').unwrap();
+        assert_eq!(position(text, b), Position::new(1, 8));
+        assert_eq!(offset(text, Position::new(1, 8)), b);
+        assert_eq!(offset(text, Position::new(1, 100)), text.find("\n}").unwrap());
+        assert_eq!(offset(text, Position::new(7, 0)), text.len());
+    }
+
+    #[test]
+    fn test_utf16() {
+        let text = "// 𝄞 é\nA";
+        let a = text.find('A').unwrap();
+        assert_eq!(position(text, a), Position::new(1, 0));
+        assert_eq!(position(text, text.find('é').unwrap()), Position::new(0, 6));
+        assert_eq!(offset(text, Position::new(0, 6)), text.find('é').unwrap());
+    }
+}
@@ -0,0 +1,415 @@
+use crate::analysis::Analysis;
+use crate::position::{offset, range};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, LogMessage,
+    Notification as NotificationType, PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, GotoDefinition, HoverRequest, Rename, Request as RequestType,
+};
+use lsp_types::{
+    CompletionItem, CompletionOptions, CompletionParams, CompletionResponse, Diagnostic,
+    DiagnosticSeverity, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents,
+    HoverParams, HoverProviderCapability, InitializeParams, Location, LogMessageParams,
+    MarkupContent, MarkupKind, MessageType, OneOf, PublishDiagnosticsParams, RenameParams,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url,
+    WorkspaceEdit,
+};
+use parser::diagnostic::Severity;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use util::error::{PakError, PakResult};
+use util::PAKKEN_FILE_ENDING;
+
+/// Runs the language server on stdin and stdout until the client shuts it down.
+pub fn run() -> PakResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![String::from(":"), String::from(".")]),
+            ..CompletionOptions::default()
+        }),
+        rename_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    };
+    let capabilities = serde_json::to_value(capabilities).map_err(protocol)?;
+    let params = connection.initialize(capabilities).map_err(protocol)?;
+    let params: InitializeParams = serde_json::from_value(params).map_err(protocol)?;
+    let folders = params.workspace_folders.unwrap_or_default().into_iter().map(|f| f.uri);
+    let roots = folders.chain(params.root_uri).filter_map(|uri| uri.to_file_path().ok()).collect();
+
+    // The server has to be dropped before joining, as the writer runs until the connection is
+    // closed.
+    let server = Server { connection, documents: HashMap::new(), published: vec![], roots };
+    server.serve()?;
+    io_threads.join()?;
+    Ok(())
+}
+
+fn protocol<E: Display>(err: E) -> PakError {
+    PakError::CustomError(format!("language server: {}", err))
+}
+
+/// A document opened in the editor.
+struct Document {
+    analysis: Analysis,
+    /// Completions of the last analysis which could resolve the model, as the model is usually
+    /// incomplete while typing.
+    completions: Vec<CompletionItem>,
+}
+
+struct Server {
+    connection: Connection,
+    documents: HashMap<Url, Document>,
+    /// Files which diagnostics have been published for, so they can be cleared.
+    published: Vec<Url>,
+    /// The folders of the workspace, whose files may import the open documents.
+    roots: Vec<PathBuf>,
+}
+
+impl Server {
+    fn serve(mut self) -> PakResult<()> {
+        while let Ok(message) = self.connection.receiver.recv() {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request).map_err(protocol)? {
+                        return Ok(());
+                    }
+                    let response = self.request(request);
+                    self.send(Message::Response(response))?;
+                },
+                Message::Notification(notification) => {
+                    // A notification which cannot be handled, e.g. one opening an unsaved
+                    // document without a file, is logged and leaves the others unaffected.
+                    if let Err(err) = self.notification(notification) {
+                        self.log(err.to_string())?;
+                    }
+                },
+                Message::Response(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn send(&self, message: Message) -> PakResult<()> {
+        self.connection.sender.send(message).map_err(protocol)
+    }
+
+    /// Shows `message` in the log of the client.
+    fn log(&self, message: String) -> PakResult<()> {
+        let params = LogMessageParams { typ: MessageType::ERROR, message };
+        let notification = Notification::new(LogMessage::METHOD.to_owned(), params);
+        self.send(Message::Notification(notification))
+    }
+
+    fn request(&mut self, request: Request) -> Response {
+        match request.method.as_str() {
+            GotoDefinition::METHOD => self.handle::<GotoDefinition>(request, Server::definition),
+            HoverRequest::METHOD => self.handle::<HoverRequest>(request, Server::hover),
+            Completion::METHOD => self.handle::<Completion>(request, Server::completion),
+            Rename::METHOD => self.handle::<Rename>(request, Server::rename),
+            method => {
+                let message = format!("method `{}` is not supported", method);
+                Response::new_err(request.id, ErrorCode::MethodNotFound as i32, message)
+            },
+        }
+    }
+
+    fn handle<R: RequestType>(
+        &mut self, request: Request, handler: fn(&Self, R::Params) -> Result<R::Result, String>,
+    ) -> Response {
+        let id = request.id.clone();
+        match request.extract::<R::Params>(R::METHOD) {
+            Ok((id, params)) => match handler(self, params) {
+                Ok(result) => Response::new_ok(id, result),
+                Err(message) => Response::new_err(id, ErrorCode::InvalidParams as i32, message),
+            },
+            Err(err) => Response::new_err(id, ErrorCode::InvalidParams as i32, err.to_string()),
+        }
+    }
+
+    fn notification(&mut self, notification: Notification) -> PakResult<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = extract::<DidOpenTextDocument>(notification)?;
+                let document = params.text_document;
+                self.update(document.uri, document.text.as_str())
+            },
+            DidChangeTextDocument::METHOD => {
+                let params = extract::<DidChangeTextDocument>(notification)?;
+                // The whole text is sent on every change.
+                match params.content_changes.last() {
+                    Some(change) => self.update(params.text_document.uri, change.text.as_str()),
+                    None => Ok(()),
+                }
+            },
+            DidCloseTextDocument::METHOD => {
+                let params = extract::<DidCloseTextDocument>(notification)?;
+                self.documents.remove(&params.text_document.uri);
+                self.publish(params.text_document.uri, vec![])
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Analyses the new `text` of the document at `uri` and publishes its diagnostics.
+    fn update(&mut self, uri: Url, text: &str) -> PakResult<()> {
+        let file = uri.to_file_path().map_err(|_| protocol(format!("{} is not a file", uri)))?;
+        let analysis = Analysis::new(text, file.as_path());
+        let completions = match (analysis.package.is_some(), self.documents.remove(&uri)) {
+            (false, Some(previous)) => previous.completions,
+            _ => analysis.completions(),
+        };
+
+        let mut diagnostics: HashMap<Url, Vec<Diagnostic>> = HashMap::new();
+        for diagnostic in analysis.diagnostics.iter() {
+            // Problems without a file are reported in the analysed document.
+            let file = diagnostic.file.clone().unwrap_or_else(|| file.clone());
+            let source = analysis.source(file.as_path()).unwrap_or("");
+            let mut message = diagnostic.error.to_string();
+            for note in diagnostic.notes.iter().chain(diagnostic.help.iter()) {
+                message += format!("\n{}", note).as_str();
+            }
+            let converted = Diagnostic {
+                range: diagnostic.span.map(|s| range(source, s)).unwrap_or_default(),
+                severity: Some(match diagnostic.severity {
+                    Severity::Error => DiagnosticSeverity::ERROR,
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                }),
+                source: Some(String::from("pakken")),
+                message,
+                ..Diagnostic::default()
+            };
+            let target = url(&uri, &analysis, file.as_path()).map_err(protocol)?;
+            diagnostics.entry(target).or_default().push(converted);
+        }
+        diagnostics.entry(uri.clone()).or_default();
+
+        self.documents.insert(uri, Document { analysis, completions });
+        for stale in std::mem::take(&mut self.published) {
+            if !diagnostics.contains_key(&stale) {
+                self.publish(stale, vec![])?;
+            }
+        }
+        for (uri, diagnostics) in diagnostics {
+            self.published.push(uri.clone());
+            self.publish(uri, diagnostics)?;
+        }
+        Ok(())
+    }
+
+    fn publish(&self, uri: Url, diagnostics: Vec<Diagnostic>) -> PakResult<()> {
+        let params = PublishDiagnosticsParams { uri, diagnostics, version: None };
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_owned(), params);
+        self.send(Message::Notification(notification))
+    }
+
+    fn document(&self, uri: &Url) -> Result<&Document, String> {
+        self.documents.get(uri).ok_or_else(|| format!("{} is not open", uri))
+    }
+
+    fn definition(
+        &self, params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>, String> {
+        let position = params.text_document_position_params;
+        let document = self.document(&position.text_document.uri)?;
+        let analysis = &document.analysis;
+        let text = analysis.source(analysis.file.as_path()).unwrap_or("");
+        let declaration = analysis
+            .symbol_at(offset(text, position.position))
+            .and_then(|entity| analysis.declaration(&entity));
+        match declaration {
+            Some((file, span)) => {
+                let source = analysis.source(file.as_path()).unwrap_or("");
+                let uri = url(&position.text_document.uri, analysis, file.as_path())?;
+                let location = Location::new(uri, range(source, span));
+                Ok(Some(GotoDefinitionResponse::Scalar(location)))
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn hover(&self, params: HoverParams) -> Result<Option<Hover>, String> {
+        let position = params.text_document_position_params;
+        let document = self.document(&position.text_document.uri)?;
+        let analysis = &document.analysis;
+        let text = analysis.source(analysis.file.as_path()).unwrap_or("");
+        let hover = analysis.hover(offset(text, position.position)).map(|value| Hover {
+            contents: HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown, value }),
+            range: None,
+        });
+        Ok(hover)
+    }
+
+    fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>, String> {
+        let document = self.document(&params.text_document_position.text_document.uri)?;
+        Ok(Some(CompletionResponse::Array(document.completions.clone())))
+    }
+
+    fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>, String> {
+        let position = params.text_document_position;
+        let document = self.document(&position.text_document.uri)?;
+        let analysis = &document.analysis;
+        let text = analysis.source(analysis.file.as_path()).unwrap_or("");
+        let offset = offset(text, position.position);
+        let edits = analysis.rename(offset, params.new_name.as_str())?;
+
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for (file, span) in edits {
+            let source = analysis.source(file.as_path()).unwrap_or("");
+            let edit = TextEdit::new(range(source, span), params.new_name.clone());
+            let uri = url(&position.text_document.uri, analysis, file.as_path())?;
+            changes.entry(uri).or_default().push(edit);
+        }
+        if let Some((namespace, name)) = analysis.exported(offset) {
+            let new_name = params.new_name.as_str();
+            self.rename_importers(
+                analysis,
+                namespace.as_str(),
+                name.as_str(),
+                new_name,
+                &mut changes,
+            )?;
+        }
+        Ok(Some(WorkspaceEdit { changes: Some(changes), ..WorkspaceEdit::default() }))
+    }
+
+    /// Adds the edits renaming the entity `name` of `namespace` in the files of the workspace
+    /// importing its namespace, which the `analysis` of the renamed document does not load.
+    fn rename_importers(
+        &self, analysis: &Analysis, namespace: &str, name: &str, new_name: &str,
+        changes: &mut HashMap<Url, Vec<TextEdit>>,
+    ) -> Result<(), String> {
+        for file in self.workspace_files(analysis.file.as_path()) {
+            let uri = Url::from_file_path(file.as_path())
+                .map_err(|_| format!("{} is not a file", file.display()))?;
+            if changes.contains_key(&uri) {
+                continue;
+            }
+            let text = match self.documents.get(&uri) {
+                Some(document) => document
+                    .analysis
+                    .source(document.analysis.file.as_path())
+                    .unwrap_or("")
+                    .to_owned(),
+                None => fs::read_to_string(file.as_path()).unwrap_or_default(),
+            };
+            if !text.contains(namespace) {
+                continue;
+            }
+            let importer = Analysis::new(text.as_str(), file.as_path());
+            if importer.package.is_none() {
+                return Err(format!(
+                    "`{}` cannot be renamed in {}, as it contains errors",
+                    name,
+                    file.display()
+                ));
+            }
+            for (_, span) in importer.references_to(namespace, name) {
+                let edit = TextEdit::new(range(text.as_str(), span), new_name.to_owned());
+                changes.entry(uri.clone()).or_default().push(edit);
+            }
+        }
+        Ok(())
+    }
+
+    /// The model files of the workspace other than `file`, or the files next to it if the client
+    /// opened no folder.
+    fn workspace_files(&self, file: &Path) -> Vec<PathBuf> {
+        let mut files = vec![];
+        let parent = file.parent().map(Path::to_path_buf);
+        let roots =
+            if self.roots.is_empty() { parent.into_iter().collect() } else { self.roots.clone() };
+        for root in roots {
+            collect(root.as_path(), &mut files);
+        }
+        let canonical = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+        files.retain(|f| f != &canonical);
+        files.sort();
+        files.dedup();
+        files
+    }
+}
+
+fn extract<N: NotificationType>(notification: Notification) -> PakResult<N::Params> {
+    notification.extract::<N::Params>(N::METHOD).map_err(protocol)
+}
+
+/// Adds the model files within `dir` and its subdirectories to `files`. Hidden directories, like
+/// `.git`, are left out.
+fn collect(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        let name = path.file_name().and_then(|n| n.to_str());
+        let hidden = matches!(name, Some(n) if n.starts_with('.'));
+        let model =
+            matches!(name, Some(n) if n.ends_with(PAKKEN_FILE_ENDING) || n.ends_with(".pakken"));
+        if path.is_dir() && !hidden {
+            collect(path.as_path(), files);
+        } else if model {
+            files.push(path.canonicalize().unwrap_or(path));
+        }
+    }
+}
+
+/// The url of `file`, which is either the document at `uri` or a file it imports.
+fn url(uri: &Url, analysis: &Analysis, file: &Path) -> Result<Url, String> {
+    if file == analysis.file {
+        return Ok(uri.clone());
+    }
+    let absolute: PathBuf = match file.canonicalize() {
+        Ok(canonical) => canonical,
+        Err(_) => file.to_path_buf(),
+    };
+    Url::from_file_path(absolute.as_path()).map_err(|_| format!("{} is not a file", file.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::Server;
+    use lsp_server::{Connection, Message, Notification, Request};
+    use lsp_types::notification::{DidOpenTextDocument, Exit, LogMessage, Notification as _};
+    use lsp_types::request::{HoverRequest, Request as _, Shutdown};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_document_without_file() {
+        let (connection, client) = Connection::memory();
+        let server =
+            Server { connection, documents: HashMap::new(), published: vec![], roots: vec![] };
+        let uri = "untitled:Untitled-1";
+        let document =
+            json!({ "uri": uri, "languageId": "pakken", "version": 1, "text": "org.a { }" });
+        let opened = json!({ "textDocument": document });
+        let position =
+            json!({ "textDocument": { "uri": uri }, "position": { "line": 0, "character": 0 } });
+        let messages = vec![
+            Message::Notification(Notification::new(
+                DidOpenTextDocument::METHOD.to_owned(),
+                opened,
+            )),
+            Message::Request(Request::new(1.into(), HoverRequest::METHOD.to_owned(), position)),
+            Message::Request(Request::new(2.into(), Shutdown::METHOD.to_owned(), ())),
+            Message::Notification(Notification::new(Exit::METHOD.to_owned(), ())),
+        ];
+        for message in messages {
+            client.sender.send(message).unwrap();
+        }
+        server.serve().expect("Should keep serving until shut down");
+
+        let received: Vec<Message> = client.receiver.try_iter().collect();
+        assert!(matches!(&received[0], Message::Notification(n) if n.method == LogMessage::METHOD));
+        assert!(matches!(&received[1], Message::Response(r) if r.error.is_some()));
+        assert!(matches!(&received[2], Message::Response(r) if r.error.is_none()));
+    }
+}
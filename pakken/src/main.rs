@@ -5,9 +5,10 @@ use clap::{load_yaml, App, AppSettings::ColoredHelp, AppSettings::SubcommandRequ
 use colored::Colorize;
 use generator::{Generator, GeneratorBuilder};
 use once_cell::sync::Lazy;
-use parser::parse;
+use parser::validation::check_annotations;
+use parser::{check_file, format_file, parse};
 use std::fs::{create_dir, remove_dir, File};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::Mutex;
 use std::{fs, io, process};
@@ -36,6 +37,11 @@ fn main() {
     match pakken(&matches) {
         Ok(_) => (),
         Err(err) => {
+            if let PakError::Diagnostics(diagnostics) = &err {
+                for diagnostic in diagnostics {
+                    eprintln!("{}\n", diagnostic);
+                }
+            }
             LOGGER.error("Fatal", err.to_string().as_str());
             process::exit(1);
         },
@@ -43,9 +49,13 @@ fn main() {
 }
 
 fn pakken(matches: &ArgMatches) -> PakResult<()> {
-    load_targets()?;
-
     let sub = matches.subcommand();
+    if sub.0 == "lsp" {
+        // The protocol is spoken over stdout, so nothing may be logged.
+        return lsp::run();
+    }
+
+    load_targets()?;
 
     match sub.0 {
         "new" => {
@@ -57,6 +67,7 @@ fn pakken(matches: &ArgMatches) -> PakResult<()> {
             new(name, path.as_path(), sub.1.unwrap())
         },
         "gen" => generate(sub.1.unwrap()),
+        "fmt" => fmt(sub.1.unwrap()),
         _ => {
             let path = Path::new("./parser/test/example.pakken");
             let file = fs::read_to_string(path.canonicalize().unwrap());
@@ -177,6 +188,7 @@ pub fn generate(matches: &ArgMatches) -> PakResult<()> {
         return Err(PakError::TargetNotFound("NotSpecified".to_owned()));
     }
     let target = matches.value_of("target").unwrap();
+    check_model(target)?;
 
     // This should create a genmodel file which basically binds the ast to the target model and resolved if something should be overwritten or not
     let mut generator_file = String::from(target);
@@ -205,3 +217,50 @@ pub fn generate(matches: &ArgMatches) -> PakResult<()> {
 
     Ok(())
 }
+
+pub fn fmt(matches: &ArgMatches) -> PakResult<()> {
+    let files: Vec<PathBuf> = match matches.values_of("files") {
+        Some(files) => files.map(PathBuf::from).collect(),
+        None => vec![Project::read()?.model],
+    };
+
+    let mut unformatted = vec![];
+    for file in &files {
+        let code = fs::read_to_string(file)?;
+        let formatted = format_file(file)?;
+        if formatted == code {
+            continue;
+        }
+        if matches.is_present("check") {
+            LOGGER.warn("Format", format!("{} is not formatted", file.display()).as_str());
+            unformatted.push(file.display().to_string());
+        } else {
+            fs::write(file, formatted)?;
+            LOGGER.info("Format", format!("formatted {}", file.display()).as_str());
+        }
+    }
+
+    if !unformatted.is_empty() {
+        return Err(PakError::NotFormatted(unformatted.join(", ")));
+    }
+    Ok(())
+}
+
+/// Validates the model of the project before generating, so warnings are shown even though the
+/// code can be generated.
+fn check_model(target_name: &str) -> PakResult<()> {
+    let project = Project::read()?;
+    let (package, mut diagnostics) = check_file(project.model.as_path());
+    let package = match package {
+        Some(package) if !diagnostics.has_errors() => package,
+        _ => return Err(PakError::from(diagnostics)),
+    };
+    let repository = TARGET_REPO.lock().unwrap();
+    let target = repository.find(target_name)?;
+    let targets = repository.list();
+    check_annotations(&package, target_name, target.annotations(), &targets, &mut diagnostics);
+    for warning in diagnostics.render() {
+        eprintln!("{}\n", warning);
+    }
+    Ok(())
+}
@@ -1,84 +1,106 @@
+use crate::diagnostic::{suggest, Diagnostic, Diagnostics};
 use crate::error::ParserError;
-use crate::ParserResult;
-use ast::{Attribute, Entity, Multiplicity, Namespace, Operation, Parameter, Structure};
+use ast::{
+    split_qualified, Annotation, Attribute, Constraint, Entity, Identifier, Identifying, Inverse,
+    Key, Multiplicity, Namespace, Operation, OperationKind, Ownership, Parameter, Reference, Span,
+    Structure, StructureKind, Value,
+};
 use std::rc::Rc;
 
+/// A parsed file whose namespaces are not resolved yet.
+#[derive(Debug, Clone)]
+pub struct DanglingFile {
+    pub imports: Vec<Import>,
+    pub namespaces: Vec<Namespace>,
+}
+
+/// Makes the namespace `namespace` of the file at `path` available to the importing file.
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub namespace: Identifier,
+    pub path: String,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone)]
 pub struct DanglingStructure {
     pub name: String,
-    pub parent: Option<String>,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub kind: StructureKind,
+    pub parameters: Vec<Rc<Entity>>,
+    pub parent: Option<Reference>,
+    pub interfaces: Vec<Reference>,
     pub attributes: Vec<DanglingAttribute>,
     pub operations: Vec<DanglingOperation>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct DanglingAttribute {
     pub name: String,
-    pub entity: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub entity: Reference,
     pub multiplicity: Multiplicity,
+    pub key: Option<Key>,
+    pub ownership: Ownership,
+    pub inverse: Option<Inverse>,
+    pub constraints: Vec<Constraint>,
+    pub default: Option<Value>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct DanglingParameter {
     pub name: String,
-    pub entity: String,
+    pub entity: Reference,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct DanglingOperation {
     pub name: String,
-    pub returns: Option<String>,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub returns: Option<Reference>,
     pub parameter: Vec<DanglingParameter>,
+    pub kind: Option<OperationKind>,
+    pub emits: Vec<Reference>,
+    pub span: Span,
 }
 
+/// Converts a parsed declaration into its AST node.
+///
+/// The references of the undangled node are not bound yet, as the entities they name might be
+/// declared later on. Once every entity is part of the namespace they are bound by `resolve`.
 pub trait Undangle {
     type Undangled;
 
-    fn undangle(&self, namespace: &Namespace) -> ParserResult<Self::Undangled>;
-    fn resolve(identifier: &str, namespace: &Namespace) -> ParserResult<Rc<Entity>> {
-        if let Some(entity) = namespace.find_entity(identifier.to_owned()) {
-            Ok(entity)
-        } else {
-            Err(ParserError::InvalidEntity(identifier.to_owned()))
-        }
-    }
+    fn undangle(&self) -> Self::Undangled;
 }
 
 impl Undangle for DanglingParameter {
     type Undangled = Parameter;
 
-    fn undangle(&self, namespace: &Namespace) -> ParserResult<Self::Undangled> {
-        let result: ParserResult<Rc<Entity>> =
-            Self::resolve(self.entity.as_str(), namespace) as ParserResult<Rc<Entity>>;
-        let resolved = result?;
-        Ok(Parameter { name: self.name.clone(), entity: resolved })
+    fn undangle(&self) -> Self::Undangled {
+        Parameter { name: self.name.clone(), entity: self.entity.clone(), span: self.span }
     }
 }
 
 impl Undangle for DanglingOperation {
     type Undangled = Operation;
 
-    fn undangle(&self, namespace: &Namespace) -> ParserResult<Self::Undangled> {
-        let mut undangled_parameters = vec![];
-        for param in self.parameter.clone() {
-            undangled_parameters.push(param.undangle(namespace)?);
-        }
-        if let Some(returns) = &self.returns {
-            let result: ParserResult<Rc<Entity>> =
-                Self::resolve(returns.as_str(), namespace) as ParserResult<Rc<Entity>>;
-            let resolved = result?;
-
-            Ok(Operation {
-                name: self.name.clone(),
-                returns: Some(resolved),
-                parameter: undangled_parameters,
-            })
-        } else {
-            Ok(Operation {
-                name: self.name.clone(),
-                returns: None,
-                parameter: undangled_parameters,
-            })
+    fn undangle(&self) -> Self::Undangled {
+        Operation {
+            name: self.name.clone(),
+            docs: self.docs.clone(),
+            annotations: self.annotations.clone(),
+            returns: self.returns.clone(),
+            parameter: self.parameter.iter().map(|p| p.undangle()).collect(),
+            kind: self.kind,
+            emits: self.emits.clone(),
+            span: self.span,
         }
     }
 }
@@ -86,44 +108,98 @@ impl Undangle for DanglingOperation {
 impl Undangle for DanglingAttribute {
     type Undangled = Attribute;
 
-    fn undangle(&self, namespace: &Namespace) -> ParserResult<Self::Undangled> {
-        let result: ParserResult<Rc<Entity>> =
-            Self::resolve(self.entity.as_str(), namespace) as ParserResult<Rc<Entity>>;
-        let resolved = result?;
-        Ok(Attribute { name: self.name.clone(), entity: resolved, multiplicity: self.multiplicity })
+    fn undangle(&self) -> Self::Undangled {
+        Attribute {
+            name: self.name.clone(),
+            docs: self.docs.clone(),
+            annotations: self.annotations.clone(),
+            entity: self.entity.clone(),
+            multiplicity: self.multiplicity,
+            key: self.key,
+            ownership: self.ownership,
+            inverse: self.inverse.clone(),
+            constraints: self.constraints.clone(),
+            default: self.default.clone(),
+            span: self.span,
+        }
     }
 }
 
 impl Undangle for DanglingStructure {
     type Undangled = Structure;
 
-    fn undangle(&self, namespace: &Namespace) -> ParserResult<Self::Undangled> {
-        if Self::resolve(self.name.as_str(), namespace).is_ok() {
-            return Err(ParserError::EntityAlreadyDefined(self.name.clone()));
+    fn undangle(&self) -> Self::Undangled {
+        Structure {
+            name: self.name.clone(),
+            docs: self.docs.clone(),
+            annotations: self.annotations.clone(),
+            attributes: self.attributes.iter().map(|a| a.undangle()).collect(),
+            operations: self.operations.iter().map(|o| o.undangle()).collect(),
+            kind: self.kind,
+            parameters: self.parameters.clone(),
+            parent: self.parent.clone(),
+            interfaces: self.interfaces.clone(),
+            span: self.span,
         }
+    }
+}
+
+/// Binds every reference of the entities in `namespace` to the entity it names and reports
+/// the references which cannot be resolved.
+///
+/// Unqualified names are looked up in `namespace` itself, qualified names like
+/// `billing.Invoice` in the `visible` namespaces.
+pub fn resolve(namespace: &Namespace, visible: &[&Namespace], diagnostics: &mut Diagnostics) {
+    let file = namespace.source.as_deref();
+    for entity in &namespace.entities {
+        for reference in entity.references() {
+            let identifier = reference.identifier();
+            // Type parameters are only visible in the structure declaring them.
+            let parameter = match entity.as_ref() {
+                Entity::Structure(struc) => struc.find_parameter(identifier.as_str()),
+                _ => None,
+            };
+            match parameter.or_else(|| lookup(identifier.as_str(), namespace, visible)) {
+                Some(found) => reference.bind(&found),
+                None => {
+                    let help = suggest(identifier.as_str(), &candidates(namespace, visible));
+                    let err = ParserError::InvalidEntity(identifier);
+                    let diagnostic = Diagnostic::new(err, file, Some(reference.span()));
+                    diagnostics.push(diagnostic.with_help(help));
+                },
+            }
+        }
+    }
+}
 
-        let parent = match self.parent.clone() {
-            Some(parent) => {
-                let result = Self::resolve(parent.as_str(), namespace);
-                let resolved = result?;
-                Some(resolved)
-            },
-            None => None,
-        };
-        let mut undangled_attributes = vec![];
-        for attr in self.attributes.clone() {
-            undangled_attributes.push(attr.undangle(namespace)?);
+/// Reports every entity of `namespace` which has the same identifier as an entity declared
+/// before it.
+pub fn check_duplicates(namespace: &Namespace, diagnostics: &mut Diagnostics) {
+    let file = namespace.source.as_deref();
+    for (index, entity) in namespace.entities.iter().enumerate() {
+        let identifier = entity.identifier();
+        if namespace.entities[.. index].iter().any(|e| e.identifier() == identifier) {
+            diagnostics.report(ParserError::EntityAlreadyDefined(identifier), file, entity.span());
         }
-        let mut undangled_operations = vec![];
-        for op in self.operations.clone() {
-            undangled_operations.push(op.undangle(namespace)?);
+    }
+}
+
+/// Returns the names of all entities which can be referenced from `namespace`.
+fn candidates(namespace: &Namespace, visible: &[&Namespace]) -> Vec<String> {
+    let mut candidates: Vec<String> = namespace.entities.iter().map(|e| e.identifier()).collect();
+    for other in visible.iter().filter(|n| n.identifier != namespace.identifier) {
+        for entity in &other.entities {
+            candidates.push(format!("{}.{}", other.identifier, entity.identifier()));
         }
+    }
+    candidates
+}
 
-        Ok(Structure {
-            name: self.name.clone(),
-            attributes: undangled_attributes,
-            operations: undangled_operations,
-            parent,
-        })
+fn lookup(identifier: &str, namespace: &Namespace, visible: &[&Namespace]) -> Option<Rc<Entity>> {
+    if let Some((qualifier, name)) = split_qualified(identifier) {
+        let found = visible.iter().find(|n| n.identifier == qualifier)?;
+        found.find_entity(name.to_owned())
+    } else {
+        namespace.find_entity(identifier.to_owned())
     }
 }
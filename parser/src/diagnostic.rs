@@ -0,0 +1,360 @@
+use crate::error::ParserError;
+use crate::pesten::Rule;
+use ast::{split_qualified, Span};
+use pest::error::Error as PestError;
+use pest::error::ErrorVariant;
+use pest::error::InputLocation;
+use pest::error::LineColLocation::{Pos, Span as LineColSpan};
+use std::fmt::{Display, Error, Formatter};
+use std::path::{Path, PathBuf};
+use std::slice::Iter;
+use std::vec::IntoIter;
+use util::buffer::Buffer;
+use util::error::PakError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The model cannot be used.
+    Error,
+    /// The model can be used, but probably does not do what was intended.
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem found in a model along with the place in the source where it occurred.
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub error: ParserError,
+    pub file: Option<PathBuf>,
+    pub span: Option<Span>,
+    /// Additional information, e.g. the tokens which were expected.
+    pub notes: Vec<String>,
+    /// A hint on how to fix the problem.
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(error: ParserError, file: Option<&Path>, span: Option<Span>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            error,
+            file: file.map(Path::to_path_buf),
+            span,
+            notes: vec![],
+            help: None,
+        }
+    }
+
+    /// Creates a diagnostic for a grammar error found by pest.
+    pub fn from_pest(error: PestError<Rule>, file: Option<&Path>) -> Diagnostic {
+        let (start, end) = match error.location {
+            InputLocation::Pos(pos) => (pos, pos),
+            InputLocation::Span((start, end)) => (start, end),
+        };
+        let (line, column) = match error.line_col {
+            Pos((line, column)) => (line, column),
+            LineColSpan((line, column), _) => (line, column),
+        };
+        let span = Span { start, end, line, column };
+        let mut notes = vec![];
+        if let ErrorVariant::ParsingError { positives, negatives } = &error.variant {
+            if !positives.is_empty() {
+                notes.push(format!("expected {}", enumerate(positives)));
+            }
+            if !negatives.is_empty() {
+                notes.push(format!("unexpected {}", enumerate(negatives)));
+            }
+        }
+        let mut diagnostic = Diagnostic::new(ParserError::from(error), file, Some(span));
+        diagnostic.notes = notes;
+        diagnostic
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Diagnostic {
+        self.severity = severity;
+        self
+    }
+
+    pub fn with_help(mut self, help: Option<String>) -> Diagnostic {
+        self.help = help;
+        self
+    }
+
+    fn location(&self) -> String {
+        let file = match &self.file {
+            Some(file) => file.display().to_string(),
+            None => String::from("<input>"),
+        };
+        match &self.span {
+            Some(span) => format!("{}:{}", file, span),
+            None => file,
+        }
+    }
+
+    /// Renders the diagnostic like rustc does, showing the offending line of `source`
+    /// underlined with carets.
+    pub fn render(&self, source: Option<&str>) -> String {
+        let mut buffer = Buffer::default();
+        buffer += format!("{}: {}", self.severity, self.error).as_str();
+        buffer.new_line();
+
+        // Spans without a position, like the default one, have no line to show.
+        let line = match (self.span, source) {
+            (Some(span), Some(source)) => {
+                let index = span.line.checked_sub(1);
+                index.and_then(|i| source.lines().nth(i)).map(|l| (span, l))
+            },
+            _ => None,
+        };
+        let gutter = match &line {
+            Some((span, _)) => " ".repeat(span.line.to_string().len()),
+            None => String::from(" "),
+        };
+        buffer += format!("{}--> {}", gutter, self.location()).as_str();
+
+        if let Some((span, text)) = line {
+            // Spans over several lines are underlined up to the end of the first line.
+            let spanned = source.and_then(|s| s.get(span.start .. span.end)).unwrap_or("");
+            let length = spanned.lines().next().unwrap_or("");
+            let carets = "^".repeat(length.chars().count().max(1));
+            buffer.new_line();
+            buffer += format!("{} |", gutter).as_str();
+            buffer.new_line();
+            buffer += format!("{} | {}", span.line, text).as_str();
+            buffer.new_line();
+            buffer +=
+                format!("{} | {}{}", gutter, " ".repeat(span.column.saturating_sub(1)), carets)
+                    .as_str();
+            buffer.new_line();
+            buffer += format!("{} |", gutter).as_str();
+        }
+        for note in &self.notes {
+            buffer.new_line();
+            buffer += format!("{} = note: {}", gutter, note).as_str();
+        }
+        if let Some(help) = &self.help {
+            buffer.new_line();
+            buffer += format!("{} = help: {}", gutter, help).as_str();
+        }
+        buffer.flush()
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{}: {}: {}", self.location(), self.severity, self.error)
+    }
+}
+
+/// Collects every diagnostic of a parse run, so all problems of a model are reported at once.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+    sources: Vec<(Option<PathBuf>, String)>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Diagnostics { Diagnostics { diagnostics: vec![], sources: vec![] } }
+
+    pub fn push(&mut self, diagnostic: Diagnostic) { self.diagnostics.push(diagnostic) }
+
+    pub fn report(&mut self, error: ParserError, file: Option<&Path>, span: Option<Span>) {
+        self.push(Diagnostic::new(error, file, span))
+    }
+
+    /// Keeps the source code of `file`, so diagnostics can show the lines they refer to.
+    pub fn add_source(&mut self, file: Option<&Path>, code: &str) {
+        self.sources.push((file.map(Path::to_path_buf), code.to_owned()))
+    }
+
+    pub fn source(&self, file: Option<&Path>) -> Option<&str> {
+        let found = self.sources.iter().find(|(f, _)| f.as_deref() == file);
+        found.map(|(_, code)| code.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool { self.diagnostics.is_empty() }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.severity == Severity::Error)
+    }
+
+    pub fn len(&self) -> usize { self.diagnostics.len() }
+
+    pub fn iter(&self) -> Iter<'_, Diagnostic> { self.diagnostics.iter() }
+
+    /// Renders every diagnostic along with the source it refers to.
+    pub fn render(&self) -> Vec<String> {
+        self.diagnostics.iter().map(|d| d.render(self.source(d.file.as_deref()))).collect()
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type IntoIter = IntoIter<Diagnostic>;
+    type Item = Diagnostic;
+
+    fn into_iter(self) -> Self::IntoIter { self.diagnostics.into_iter() }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{}", self.render().join("\n\n"))
+    }
+}
+
+impl From<ParserError> for Diagnostics {
+    fn from(error: ParserError) -> Self {
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.report(error, None, None);
+        diagnostics
+    }
+}
+
+impl From<Diagnostics> for PakError {
+    fn from(diagnostics: Diagnostics) -> Self { PakError::Diagnostics(diagnostics.render()) }
+}
+
+/// Suggests the candidate most similar to the misspelled `name`, comparing only the entity part
+/// of qualified names.
+pub fn suggest(name: &str, candidates: &[String]) -> Option<String> {
+    let unqualified =
+        |n: &'_ str| split_qualified(n).map(|(_, e)| e.to_owned()).unwrap_or(n.into());
+    let wanted = unqualified(name);
+    let threshold = (wanted.chars().count() / 3).max(1);
+    candidates
+        .iter()
+        .filter(|c| c.as_str() != name)
+        .map(|c| (distance(wanted.as_str(), unqualified(c).as_str()), c))
+        .filter(|(d, _)| *d <= threshold)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| format!("did you mean `{}`?", c))
+}
+
+/// Levenshtein distance between `a` and `b`.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0 ..= b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn enumerate(rules: &[Rule]) -> String {
+    let described: Vec<&str> = rules.iter().map(describe).collect();
+    match described.split_last() {
+        Some((last, [])) => (*last).to_owned(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+fn describe(rule: &Rule) -> &'static str {
+    match rule {
+        Rule::spacename | Rule::namespace => "a namespace",
+        Rule::entityname | Rule::entitytype | Rule::entity => "an entity",
+        Rule::scalar | Rule::scalarkeyword => "a scalar",
+        Rule::union | Rule::unionkeyword => "a union",
+        Rule::unionmember => "a member of a union",
+        Rule::service | Rule::servicekeyword => "a service",
+        Rule::operationkind => "`query` or `command`",
+        Rule::emits | Rule::emittedevent => "an event",
+        Rule::typename | Rule::parententityname | Rule::typeref => "a type",
+        Rule::typearguments => "type arguments",
+        Rule::typeparameter | Rule::typeparameters => "a type parameter",
+        Rule::structurekind => "`abstract`, `interface` or `event`",
+        Rule::interfacename => "an interface",
+        Rule::attributename | Rule::attribute => "an attribute",
+        Rule::keykind => "`key` or `generated key`",
+        Rule::composition => "`contains`",
+        Rule::inverse | Rule::inversename => "an inverse attribute",
+        Rule::operationname | Rule::operation => "an operation",
+        Rule::parametername | Rule::parameter | Rule::parameterlist => "a parameter",
+        Rule::feature => "an attribute or an operation",
+        Rule::enummembername | Rule::enummember => "an enum member",
+        Rule::enummembervalue => "an enum value",
+        Rule::multiplicity | Rule::multimult | Rule::listmult | Rule::optionalmult => {
+            "a multiplicity"
+        },
+        Rule::singlemult => "a number",
+        Rule::import => "an import",
+        Rule::doc => "a doc comment",
+        Rule::annotation | Rule::annotationname => "an annotation",
+        Rule::annotationword => "a value",
+        Rule::value | Rule::double | Rule::integer | Rule::boolean | Rule::emptylist => "a value",
+        Rule::membername => "a value",
+        Rule::string | Rule::text => "a string",
+        Rule::constraints | Rule::constraint | Rule::constraintkind => "a constraint",
+        Rule::importsource | Rule::importpath => "a path",
+        Rule::EOI => "the end of the file",
+        _ => "a token",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostic::{distance, suggest, Diagnostic};
+    use crate::error::ParserError;
+    use crate::parse;
+    use ast::Span;
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("Vehicle", "Vehicle"), 0);
+        assert_eq!(distance("Vehicel", "Vehicle"), 2);
+        assert_eq!(distance("Car", "Cars"), 1);
+        assert_eq!(distance("", "Car"), 3);
+    }
+
+    #[test]
+    fn test_suggest() {
+        let candidates = vec!["Vehicle".to_owned(), "org.billing.Invoice".to_owned()];
+        assert_eq!(suggest("Vehicel", &candidates).unwrap(), "did you mean `Vehicle`?");
+        assert_eq!(suggest("Invoice", &candidates).unwrap(), "did you mean `org.billing.Invoice`?");
+        assert!(suggest("Person", &candidates).is_none());
+    }
+
+    #[test]
+    fn test_render_unknown_entity() {
+        let code = "org.mobile {\n    Vehicle { }\n    Bike : Vehicel { }\n}";
+        let rendered = parse(code).expect_err("Should not have parsed").render();
+        let expected = [
+            "error: `Vehicel` is not a valid entity.",
+            " --> <input>:3:12",
+            "  |",
+            "3 |     Bike : Vehicel { }",
+            "  |            ^^^^^^^",
+            "  |",
+            "  = help: did you mean `Vehicle`?",
+        ];
+        assert_eq!(rendered[0], expected.join("\n"));
+    }
+
+    #[test]
+    fn test_render_without_position() {
+        let diagnostic = Diagnostic::new(ParserError::NoToken, None, Some(Span::default()));
+        let rendered = diagnostic.render(Some("org.a { }"));
+        assert!(!rendered.contains(" | "), "{}", rendered);
+    }
+
+    #[test]
+    fn test_render_grammar_error() {
+        let code = "org.mobile {\n    Vehicle { name String }\n}";
+        let rendered = parse(code).expect_err("Should not have parsed").render();
+        assert!(rendered[0].contains("2 |     Vehicle { name String }"));
+        assert!(rendered[0].contains("= note: expected"));
+    }
+}
@@ -27,14 +27,149 @@ pub enum ParserError {
     /// Unhandled Error
     Unhandled,
 
-    /// File not found
-    FileNotFound,
+    /// File `{0}` not found
+    FileNotFound(String),
 
     /// `{0}` is not a valid entity.
     InvalidEntity(String),
 
     /// `{0}` is already defined. Please use another identifier.
     EntityAlreadyDefined(String),
+
+    /// Namespace `{0}` is already defined.
+    NamespaceAlreadyDefined(String),
+
+    /// Namespace `{0}` could not be found in `{1}`.
+    NamespaceNotFound(String, String),
+
+    /// `{1}` is declared more than once in `{0}`.
+    DuplicateFeature(String, String),
+
+    /// Operation `{1}` of `{0}` is overloaded, which is not supported by every target.
+    OverloadedOperation(String, String),
+
+    /// Operation `{1}` is marked as a query or command, but `{0}` has no key to look up the object it is called on.
+    UnkeyedOperation(String, String),
+
+    /// Parameter `{1}` is declared more than once in operation `{0}`.
+    DuplicateParameter(String, String),
+
+    /// The lower bound `{0}` is greater than the upper bound `{1}`.
+    InvalidMultiplicity(usize, usize),
+
+    /// `{0}.{1}` of type `{2}` conflicts with `{3}.{1}` of type `{4}`.
+    ConflictingAttribute(String, String, String, String, String),
+
+    /// `{0}.{1}` with multiplicity {2} does not narrow `{3}.{1}` with multiplicity {4}.
+    WideningOverride(String, String, String, String, String),
+
+    /// Type parameter `{1}` is declared more than once by `{0}`.
+    DuplicateTypeParameter(String, String),
+
+    /// `{0}` takes {1} type arguments, but {2} are given.
+    TypeArgumentCount(String, usize, usize),
+
+    /// Maps can only be keyed by scalars and enums, but `{0}` is neither.
+    InvalidMapKey(String),
+
+    /// `{0}` inherits from itself.
+    CyclicInheritance(String),
+
+    /// `{0}` can only inherit from a structure, but `{1}` is not one.
+    InvalidParent(String, String),
+
+    /// Member `{1}` is declared more than once in `{0}`.
+    DuplicateEnumMember(String, String),
+
+    /// Value `{1}` is used by more than one member of `{0}`.
+    DuplicateEnumValue(String, String),
+
+    /// `{0}` has no members.
+    EmptyEnum(String),
+
+    /// Member `{1}` of `{0}` has a value of type {2}, but the members before it have values of type {3}.
+    MixedEnumValues(String, String, String, String),
+
+    /// `{0}` is not a valid number.
+    InvalidNumber(String),
+
+    /// `{1}` is not a valid default value for `{0}` of type `{2}`.
+    InvalidDefault(String, String, String),
+
+    /// `{1}` is not a valid constraint for `{0}` of type `{2}`.
+    InvalidConstraint(String, String, String),
+
+    /// `{1}` is constrained more than once for `{0}`.
+    DuplicateConstraint(String, String),
+
+    /// `{1}` contradicts `{2}` of `{0}`.
+    ContradictingConstraints(String, String, String),
+
+    /// The default value of `{0}` violates `{1}`.
+    DefaultViolatesConstraint(String, String),
+
+    /// Scalar `{0}` can only be based on a built-in scalar, but `{1}` is not one.
+    InvalidScalarBase(String, String),
+
+    /// Key `{0}.{1}` has to be exactly one value.
+    InvalidKey(String, String),
+
+    /// Key `{0}.{1}` has to be a scalar or an enum, but `{2}` is neither.
+    InvalidKeyType(String, String, String),
+
+    /// A generated key has to identify the instances of `{0}` alone.
+    GeneratedCompositeKey(String),
+
+    /// `{0}.{1}` cannot be a relationship, as `{2}` is not a structure.
+    NotARelationship(String, String, String),
+
+    /// `{2}` has no attribute `{3}`, which `{0}.{1}` names as its inverse.
+    MissingInverse(String, String, String, String),
+
+    /// `{2}.{3}` cannot be the inverse of `{0}.{1}`, as it references `{4}` instead of `{0}`.
+    MismatchedInverse(String, String, String, String, String),
+
+    /// `{2}.{3}` names `{4}` as its inverse instead of `{1}` of `{0}`.
+    ConflictingInverse(String, String, String, String, String),
+
+    /// `{0}.{1}` and `{2}.{3}` cannot both contain the other end of their relationship.
+    MutualComposition(String, String, String, String),
+
+    /// `{2}.{3}` has to be a single value, as the parts `{0}.{1}` contains belong to one `{0}`.
+    SharedPart(String, String, String, String),
+
+    /// `{0}` is a service and cannot be used as a type.
+    ServiceAsType(String),
+
+    /// Event `{0}` can only declare attributes, without type parameters, parents, interfaces or operations.
+    InvalidEvent(String),
+
+    /// `{0}` cannot inherit from event `{1}`, events cannot be extended.
+    InheritsEvent(String, String),
+
+    /// `{1}` is emitted by operation `{0}`, but it is not an event.
+    NotAnEvent(String, String),
+
+    /// `{1}` cannot be a member of union `{0}`, only concrete structures can.
+    InvalidUnionMember(String, String),
+
+    /// `{1}` is a member of union `{0}` more than once.
+    DuplicateUnionMember(String, String),
+
+    /// Interface `{0}` cannot inherit from `{1}`, interfaces can only implement other interfaces.
+    InterfaceWithParent(String, String),
+
+    /// `{0}` cannot inherit from interface `{1}`, it has to implement it instead.
+    InheritsInterface(String, String),
+
+    /// `{0}` can only implement interfaces, but `{1}` is not one.
+    NotAnInterface(String, String),
+
+    /// `{0}` does not provide `{1}` of interface `{2}`.
+    MissingFeature(String, String, String),
+
+    /// `@{0}` is not understood by target `{1}` and is ignored.
+    UnknownAnnotation(String, String),
 }
 
 impl Display for Rule {
@@ -0,0 +1,813 @@
+use crate::dangling::{DanglingFile, Import};
+use crate::diagnostic::{Diagnostic, Diagnostics};
+use crate::error::ParserError;
+use crate::pesten::{pairs, Parsable, Rule};
+use ast::{
+    Annotation, Attribute, CustomScalar, Entity, Enum, EnumMember, Identifying, Multiplicity,
+    Namespace, Operation, Ownership, Service, Span, Structure, StructureKind, Union,
+};
+use std::path::Path;
+use util::buffer::Buffer;
+
+const INDENT: &str = "    ";
+
+/// Rewrites `code` of `file` in the canonical layout, keeping its comments.
+///
+/// Only the grammar is checked, so files can be formatted without their imports.
+pub fn format_code(code: &str, file: Option<&Path>) -> Result<String, Diagnostics> {
+    let mut diagnostics = Diagnostics::new();
+    diagnostics.add_source(file, code);
+    let parsed = match pairs(Rule::pakken, code) {
+        Ok(mut pairs) => match pairs.next() {
+            Some(pair) => DanglingFile::from_pest(pair),
+            None => Err(ParserError::NoToken),
+        },
+        Err(err) => {
+            diagnostics.push(Diagnostic::from_pest(err, file));
+            return Err(diagnostics);
+        },
+    };
+    match parsed {
+        Ok(dangling) => {
+            let mut printer = Printer::new(code);
+            printer.file(&dangling);
+            Ok(printer.flush())
+        },
+        Err(err) => {
+            diagnostics.report(err, file, None);
+            Err(diagnostics)
+        },
+    }
+}
+
+/// Emits `namespace` as DSL in the canonical layout.
+pub fn print(namespace: &Namespace) -> String {
+    let mut printer = Printer::new("");
+    printer.node(namespace.span, false, false, |p| p.namespace(namespace));
+    printer.flush()
+}
+
+/// Emits the declaration of `entity` in the canonical layout. Scalars and maps are built in and
+/// have no declaration.
+pub fn print_entity(entity: &Entity) -> String {
+    let mut printer = Printer::new("");
+    let span = entity.span().unwrap_or_default();
+    printer.node(span, false, false, |p| match entity {
+        Entity::Structure(struc) => p.structure(struc),
+        Entity::Enum(enumeration) => p.enumeration(enumeration),
+        Entity::CustomScalar(custom) => p.scalar(custom),
+        Entity::Union(union) => p.union(union),
+        Entity::Service(service) => p.service(service),
+        Entity::Scalar(scalar) => p.buffer += scalar.to_string().as_str(),
+        Entity::TypeParameter(parameter) => p.buffer += parameter.name.as_str(),
+        Entity::Map => p.buffer += "Map",
+    });
+    printer.flush()
+}
+
+/// Emits `attr` as a single line.
+pub fn print_attribute(attr: &Attribute) -> String {
+    let mut printer = Printer::new("");
+    printer.attribute(attr);
+    printer.flush()
+}
+
+/// Emits `op` as a single line.
+pub fn print_operation(op: &Operation) -> String {
+    let mut printer = Printer::new("");
+    printer.operation(op);
+    printer.flush()
+}
+
+/// A `//` comment of the source.
+struct Comment {
+    start: usize,
+    end: usize,
+}
+
+/// Writes the canonical layout. Comments of the source are emitted before the first node
+/// following them, or behind a node if they started on the same line.
+struct Printer<'a> {
+    code: &'a str,
+    comments: Vec<Comment>,
+    /// End of the last node or comment which was written.
+    last: usize,
+    buffer: Buffer,
+    /// Whether anything has been written yet.
+    started: bool,
+    /// Whether a block has just been opened, so no blank line is kept.
+    opened: bool,
+    /// Whether the next line has to be preceded by a blank line.
+    blank: bool,
+}
+
+impl<'a> Printer<'a> {
+    fn new(code: &'a str) -> Printer<'a> {
+        Printer {
+            code,
+            comments: comments(code),
+            last: 0,
+            buffer: Buffer::with_indent(INDENT),
+            started: false,
+            opened: false,
+            blank: false,
+        }
+    }
+
+    fn flush(mut self) -> String {
+        self.leading(self.code.len());
+        if self.started {
+            self.buffer += "\n";
+        }
+        self.buffer.flush()
+    }
+
+    fn file(&mut self, file: &DanglingFile) {
+        for import in &file.imports {
+            self.node(import.span, false, false, |p| p.import(import));
+        }
+        for (index, namespace) in file.namespaces.iter().enumerate() {
+            let blank = index > 0 || !file.imports.is_empty();
+            self.node(namespace.span, blank, false, |p| p.namespace(namespace));
+        }
+    }
+
+    fn import(&mut self, import: &Import) {
+        self.buffer += format!("import {} from \"{}\"", import.namespace, import.path).as_str();
+    }
+
+    fn namespace(&mut self, namespace: &Namespace) {
+        let entities: Vec<&Entity> = namespace
+            .entities
+            .iter()
+            .map(|e| e.as_ref())
+            .filter(|e| Entity::span(e).is_some())
+            .collect();
+        self.buffer += namespace.identifier.as_str();
+        self.block(namespace.span, entities.as_slice(), false, |p, entity| match entity {
+            Entity::Structure(struc) => p.structure(struc),
+            Entity::Enum(enumeration) => p.enumeration(enumeration),
+            Entity::CustomScalar(custom) => p.scalar(custom),
+            Entity::Union(union) => p.union(union),
+            Entity::Service(service) => p.service(service),
+            Entity::Scalar(_) | Entity::TypeParameter(_) | Entity::Map => (),
+        });
+    }
+
+    fn structure(&mut self, struc: &Structure) {
+        self.docs(&struc.docs);
+        self.annotations(&struc.annotations);
+        if struc.kind != StructureKind::Concrete {
+            self.buffer += format!("{} ", struc.kind).as_str();
+        }
+        self.buffer += struc.name.as_str();
+        if struc.is_generic() {
+            let parameters: Vec<String> = struc.parameters.iter().map(|p| p.identifier()).collect();
+            self.buffer += format!("<{}>", parameters.join(", ")).as_str();
+        }
+        if let Some(parent) = &struc.parent {
+            self.buffer += format!(" : {}", parent).as_str();
+        }
+        if !struc.interfaces.is_empty() {
+            let interfaces: Vec<String> = struc.interfaces.iter().map(|i| i.identifier()).collect();
+            self.buffer += format!(" implements {}", interfaces.join(", ")).as_str();
+        }
+        // Attributes and operations are kept in the order they were declared in.
+        let mut features: Vec<Feature> = struc.attributes.iter().map(Feature::Attribute).collect();
+        features.extend(struc.operations.iter().map(Feature::Operation));
+        features.sort_by_key(|f| f.span().start);
+        self.block(struc.span, features.as_slice(), true, |p, feature| match feature {
+            Feature::Attribute(attr) => p.attribute(attr),
+            Feature::Operation(op) => p.operation(op),
+        });
+    }
+
+    fn service(&mut self, service: &Service) {
+        self.docs(&service.docs);
+        self.annotations(&service.annotations);
+        self.buffer += format!("service {}", service.name).as_str();
+        self.block(service.span, service.operations.as_slice(), true, |p, op| p.operation(op));
+    }
+
+    fn attribute(&mut self, attr: &Attribute) {
+        self.docs(&attr.docs);
+        self.annotations(&attr.annotations);
+        if let Some(key) = attr.key {
+            self.buffer += format!("{} ", key).as_str();
+        }
+        if attr.ownership == Ownership::Composition {
+            self.buffer += "contains ";
+        }
+        let multiplicity = multiplicity(&attr.multiplicity);
+        self.buffer += format!("{}: {}{}", attr.name, attr.entity, multiplicity).as_str();
+        if let Some(inverse) = &attr.inverse {
+            self.buffer += format!(" inverse {}", inverse.name).as_str();
+        }
+        if !attr.constraints.is_empty() {
+            let constraints: Vec<String> = attr.constraints.iter().map(|c| c.to_string()).collect();
+            self.buffer += format!(" ({})", constraints.join(", ")).as_str();
+        }
+        if let Some(default) = &attr.default {
+            self.buffer += format!(" = {}", default).as_str();
+        }
+    }
+
+    fn operation(&mut self, op: &Operation) {
+        self.docs(&op.docs);
+        self.annotations(&op.annotations);
+        if let Some(kind) = op.kind {
+            self.buffer += format!("{} ", kind).as_str();
+        }
+        let parameter: Vec<String> =
+            op.parameter.iter().map(|p| format!("{}: {}", p.name, p.entity)).collect();
+        self.buffer += format!("{}({})", op.name, parameter.join(", ")).as_str();
+        if let Some(returns) = &op.returns {
+            self.buffer += format!(": {}", returns).as_str();
+        }
+        if !op.emits.is_empty() {
+            let events: Vec<String> = op.emits.iter().map(|e| e.identifier()).collect();
+            self.buffer += format!(" emits {}", events.join(", ")).as_str();
+        }
+    }
+
+    fn scalar(&mut self, custom: &CustomScalar) {
+        self.docs(&custom.docs);
+        self.annotations(&custom.annotations);
+        self.buffer += format!("scalar {} : {}", custom.name, custom.base.identifier()).as_str();
+    }
+
+    fn union(&mut self, union: &Union) {
+        self.docs(&union.docs);
+        self.annotations(&union.annotations);
+        let members: Vec<String> = union.members.iter().map(|m| m.identifier()).collect();
+        self.buffer += format!("union {} = {}", union.name, members.join(" | ")).as_str();
+    }
+
+    fn enumeration(&mut self, enumeration: &Enum) {
+        self.docs(&enumeration.docs);
+        self.annotations(&enumeration.annotations);
+        self.buffer += enumeration.identifier.as_str();
+        self.block(enumeration.span, enumeration.members.as_slice(), true, |p, member| {
+            p.docs(&member.docs);
+            p.annotations(&member.annotations);
+            p.buffer += member.name.as_str();
+            if let Some(value) = &member.value {
+                p.buffer += format!(" = {}", value).as_str();
+            }
+        });
+    }
+
+    /// Writes `docs` as `///` comments, each on a line of its own.
+    fn docs(&mut self, docs: &Option<String>) {
+        for line in docs.iter().flat_map(|d| d.lines()) {
+            self.buffer += "///";
+            if !line.is_empty() {
+                self.buffer += " ";
+                self.buffer += line;
+            }
+            self.buffer.new_line();
+        }
+    }
+
+    /// Writes `annotations`, each on a line of its own. Values are quoted unless they are a
+    /// single word.
+    fn annotations(&mut self, annotations: &[Annotation]) {
+        for annotation in annotations {
+            self.buffer += format!("@{}", annotation.name).as_str();
+            match &annotation.value {
+                Some(value)
+                    if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric()) =>
+                {
+                    self.buffer += format!("({})", value).as_str();
+                },
+                Some(value) => self.buffer += format!("(\"{}\")", value).as_str(),
+                None => (),
+            }
+            self.buffer.new_line();
+        }
+    }
+
+    /// Writes `items` indented within braces, ending at the end of `span`. Blocks without items
+    /// and comments are written as `{ }`.
+    fn block<T: Spanned>(
+        &mut self, span: Span, items: &[T], commas: bool, mut item: impl FnMut(&mut Self, &T),
+    ) {
+        let has_comments = matches!(self.comments.first(), Some(c) if c.start < span.end);
+        if items.is_empty() && !has_comments {
+            self.buffer += " { }";
+            return;
+        }
+        self.buffer += " {";
+        self.buffer.indent();
+        self.opened = true;
+        for (index, it) in items.iter().enumerate() {
+            let comma = commas && index + 1 < items.len();
+            self.node(it.span(), false, comma, |p| item(p, it));
+        }
+        self.leading(span.end);
+        self.buffer.unindent();
+        self.buffer.new_line();
+        self.buffer += "}";
+    }
+
+    /// Writes a node on a line of its own, preceded by its comments and followed by a comment
+    /// on the same line. If `blank` is set, a blank line is put in front of its comments.
+    fn node(&mut self, span: Span, blank: bool, comma: bool, write: impl FnOnce(&mut Self)) {
+        self.blank = blank;
+        let start = self.documented(span.start);
+        self.leading(start);
+        self.separate(start);
+        write(self);
+        if comma {
+            self.buffer += ",";
+        }
+        self.last = self.last.max(span.end);
+        self.trailing();
+    }
+
+    /// Moves `start` of a declaration in front of the `///` comments and lines of annotations
+    /// above it.
+    fn documented(&self, start: usize) -> usize {
+        let mut start = start;
+        loop {
+            let before = self.code.get(.. start).unwrap_or("").trim_end();
+            let line = before[before.rfind('\n').map_or(0, |i| i + 1) ..].trim_start();
+            if !line.starts_with("///") && !annotations_only(line) {
+                return start;
+            }
+            start = before.len() - line.len();
+        }
+    }
+
+    /// Writes the comments in front of `position` which have not been written yet.
+    fn leading(&mut self, position: usize) {
+        while matches!(self.comments.first(), Some(c) if c.start < position) {
+            let comment = self.comments.remove(0);
+            self.separate(comment.start);
+            self.buffer += &self.code[comment.start .. comment.end];
+            self.last = comment.end;
+        }
+    }
+
+    fn trailing(&mut self) {
+        let same_line = match self.comments.first() {
+            Some(comment) => {
+                let between = self.code.get(self.last .. comment.start).unwrap_or("\n");
+                !between.contains('\n')
+            },
+            None => false,
+        };
+        if same_line {
+            let comment = self.comments.remove(0);
+            self.buffer += " ";
+            self.buffer += &self.code[comment.start .. comment.end];
+            self.last = comment.end;
+        }
+    }
+
+    /// Starts a new line for whatever starts at `start`. A single blank line of the source is
+    /// kept unless a block has just been opened.
+    fn separate(&mut self, start: usize) {
+        if self.started {
+            let between = self.code.get(self.last .. start).unwrap_or("");
+            if self.blank || (!self.opened && between.matches('\n').count() > 1) {
+                self.buffer += "\n";
+            }
+            self.buffer.new_line();
+        }
+        self.started = true;
+        self.opened = false;
+        self.blank = false;
+    }
+}
+
+trait Spanned {
+    fn span(&self) -> Span;
+}
+
+impl Spanned for &Entity {
+    fn span(&self) -> Span { Entity::span(self).unwrap_or_default() }
+}
+
+impl Spanned for Operation {
+    fn span(&self) -> Span { self.span }
+}
+
+impl Spanned for EnumMember {
+    fn span(&self) -> Span { self.span }
+}
+
+enum Feature<'a> {
+    Attribute(&'a Attribute),
+    Operation(&'a Operation),
+}
+
+impl Spanned for Feature<'_> {
+    fn span(&self) -> Span {
+        match self {
+            Feature::Attribute(attr) => attr.span,
+            Feature::Operation(op) => op.span,
+        }
+    }
+}
+
+fn multiplicity(multiplicity: &Multiplicity) -> String {
+    match multiplicity {
+        Multiplicity::Single => String::new(),
+        Multiplicity::Optional => String::from("?"),
+        multiplicity => format!(" {}", multiplicity),
+    }
+}
+
+/// Finds the `//` comments of `code`, skipping the strings of imports.
+fn comments(code: &str) -> Vec<Comment> {
+    let mut comments = vec![];
+    let mut in_string = false;
+    let mut chars = code.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => in_string = !in_string,
+            // `///` starts a doc comment, which belongs to the declaration below it.
+            '/' if !in_string && code[index ..].starts_with("///") => {
+                let end = code[index ..].find('\n').map_or(code.len(), |e| index + e);
+                while matches!(chars.peek(), Some((i, _)) if *i < end) {
+                    chars.next();
+                }
+            },
+            '/' if !in_string && chars.peek().map(|(_, c)| *c) == Some('/') => {
+                let end = code[index ..].find('\n').map_or(code.len(), |e| index + e);
+                comments.push(Comment {
+                    start: index,
+                    end: index + code[index .. end].trim_end().len(),
+                });
+                while matches!(chars.peek(), Some((i, _)) if *i < end) {
+                    chars.next();
+                }
+            },
+            _ => (),
+        }
+    }
+    comments
+}
+
+/// Whether `line` consists of annotations only, e.g. `@graphql(skip) @deprecated("use b")`, and
+/// not of a declaration written on the same line as its annotations.
+fn annotations_only(line: &str) -> bool {
+    let mut rest = line.trim();
+    while let Some(annotation) = rest.strip_prefix('@') {
+        let name =
+            annotation.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(annotation.len());
+        rest = &annotation[name ..];
+        if let Some(value) = rest.strip_prefix('(') {
+            // A quoted value ends at the next quote and may contain parentheses.
+            let start = match value.strip_prefix('"') {
+                Some(quoted) => match quoted.find('"') {
+                    Some(end) => end + 2,
+                    None => return false,
+                },
+                None => 0,
+            };
+            rest = match value[start ..].find(')') {
+                Some(end) => &value[start + end + 1 ..],
+                None => return false,
+            };
+        }
+        rest = rest.trim_start();
+    }
+    rest.is_empty() && !line.trim().is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::{format_code, print};
+    use crate::parse;
+
+    fn format(code: &str) -> String { format_code(code, None).expect("Should have formatted") }
+
+    #[test]
+    fn test_format_layout() {
+        let code = "import org.b from \"b.pakken\" org.a{A:B{c:String[2,4],d:A [ + ],e( f:String \
+                    ,g:A ):A, h : String?}E{x=1,y}C{r=\"#f00\",g=  -1}}";
+        let expected = [
+            "import org.b from \"b.pakken\"",
+            "",
+            "org.a {",
+            "    A : B {",
+            "        c: String [2, 4],",
+            "        d: A [+],",
+            "        e(f: String, g: A): A,",
+            "        h: String?",
+            "    }",
+            "    E {",
+            "        x = 1,",
+            "        y",
+            "    }",
+            "    C {",
+            "        r = \"#f00\",",
+            "        g = -1",
+            "    }",
+            "}",
+            "",
+        ];
+        assert_eq!(format(code), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_format_comments() {
+        let code = "// model\norg.a {\n  // first\n  A { b: String, // trailing\n\n\n  c(): A // \
+                    last\n  // closing\n  }\n  E { }\n}\n// end\n";
+        let expected = [
+            "// model",
+            "org.a {",
+            "    // first",
+            "    A {",
+            "        b: String, // trailing",
+            "",
+            "        c(): A // last",
+            "        // closing",
+            "    }",
+            "    E { }",
+            "}",
+            "// end",
+            "",
+        ];
+        assert_eq!(format(code), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_format_docs() {
+        let code = "org.a {\n  // comment\n  ///Vehicles\n  ///   of any kind\n\n  A {\n    /// \
+                    The name\n    b: String, c()\n  }\n  /// Colors\n  E { /// Red\n red }\n}\n";
+        let expected = [
+            "org.a {",
+            "    // comment",
+            "    /// Vehicles",
+            "    ///   of any kind",
+            "    A {",
+            "        /// The name",
+            "        b: String,",
+            "        c()",
+            "    }",
+            "    /// Colors",
+            "    E {",
+            "        /// Red",
+            "        red",
+            "    }",
+            "}",
+            "",
+        ];
+        assert_eq!(format(code), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_format_annotations() {
+        let code = "org.a {\n  /// Users\n  @graphql(skip)\n  A {\n    @id id: Integer,\n    \
+                    @deprecated(\"use b\") @readonly a: String\n  }\n}\n";
+        let expected = [
+            "org.a {",
+            "    /// Users",
+            "    @graphql(skip)",
+            "    A {",
+            "        @id",
+            "        id: Integer,",
+            "        @deprecated(\"use b\")",
+            "        @readonly",
+            "        a: String",
+            "    }",
+            "}",
+            "",
+        ];
+        assert_eq!(format(code), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_format_annotations_on_one_line() {
+        let code =
+            "org.a {\n    @x A {\n        @id id: Integer,\n\n        name: String\n    }\n\n    \
+             @graphql(skip) @deprecated(\"a (b)\")\n    B { }\n\n    @x C { }\n\n    D { }\n}\n";
+        let expected = [
+            "org.a {",
+            "    @x",
+            "    A {",
+            "        @id",
+            "        id: Integer,",
+            "",
+            "        name: String",
+            "    }",
+            "",
+            "    @graphql(skip)",
+            "    @deprecated(\"a (b)\")",
+            "    B { }",
+            "",
+            "    @x",
+            "    C { }",
+            "",
+            "    D { }",
+            "}",
+            "",
+        ];
+        assert_eq!(format(code), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_format_non_ascii_annotations() {
+        let code = "org.a { A { @deprecated(\"é\") a: String } }";
+        let formatted = format(code);
+        assert!(formatted.contains("@deprecated(\"é\")"));
+        assert_eq!(format(formatted.as_str()), formatted);
+    }
+
+    #[test]
+    fn test_format_defaults() {
+        let code = "org.a {\n  A {\n    a: Boolean=true,\n    b: String [ * ] = [ ],\n    c: \
+                    Double = -1.50, d: String? = \"a // b\"\n  }\n}\n";
+        let expected = [
+            "org.a {",
+            "    A {",
+            "        a: Boolean = true,",
+            "        b: String [*] = [],",
+            "        c: Double = -1.5,",
+            "        d: String? = \"a // b\"",
+            "    }",
+            "}",
+            "",
+        ];
+        assert_eq!(format(code), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_format_constraints() {
+        let code =
+            "org.a { A { age: Integer(min 0,max 150)=18, b: String [*] ( pattern \"[a-z]+\" ) } }";
+        let expected = [
+            "org.a {",
+            "    A {",
+            "        age: Integer (min 0, max 150) = 18,",
+            "        b: String [*] (pattern \"[a-z]+\")",
+            "    }",
+            "}",
+            "",
+        ];
+        assert_eq!(format(code), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_format_large_doubles() {
+        let code = "org.a { A { a: Double (min 0.0, max 100000000000000000000.0) = \
+                    50000000000000000000.0, b: Double = 0.00000001 } }";
+        let formatted = format(code);
+        assert!(formatted.contains(
+            "a: Double (min 0.0, max 100000000000000000000.0) = 50000000000000000000.0,"
+        ));
+        assert!(formatted.contains("b: Double = 0.00000001"));
+        parse(formatted.as_str()).expect("Should parse the formatted code");
+    }
+
+    #[test]
+    fn test_format_scalars() {
+        let code = "org.a {\n  A { b: Email }\n  /// An address.\n  @unique\n  scalar  \
+                    Email:String // checked\n}\n";
+        let expected = [
+            "org.a {",
+            "    A {",
+            "        b: Email",
+            "    }",
+            "    /// An address.",
+            "    @unique",
+            "    scalar Email : String // checked",
+            "}",
+            "",
+        ];
+        assert_eq!(format(code), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_format_keys() {
+        let code =
+            "org.a {\n  A { generated   key id: UUID, key  number: String, key: Integer }\n}\n";
+        let expected = [
+            "org.a {",
+            "    A {",
+            "        generated key id: UUID,",
+            "        key number: String,",
+            "        key: Integer",
+            "    }",
+            "}",
+            "",
+        ];
+        assert_eq!(format(code), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_format_services() {
+        let code = "org.a {\n  /// Orders.\n  service  Orders{query find( n:String ):A,\n  \
+                    command   place(a:A),\n cancel() // later\n}\n  A {a: String}\n}\n";
+        let expected = [
+            "org.a {",
+            "    /// Orders.",
+            "    service Orders {",
+            "        query find(n: String): A,",
+            "        command place(a: A),",
+            "        cancel() // later",
+            "    }",
+            "    A {",
+            "        a: String",
+            "    }",
+            "}",
+            "",
+        ];
+        assert_eq!(format(code), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_format_events() {
+        let code =
+            "org.a {\n  event  Placed {a: A}\n  A {\n    place(): A emits  Placed,Placed\n  }\n}\n";
+        let expected = [
+            "org.a {",
+            "    event Placed {",
+            "        a: A",
+            "    }",
+            "    A {",
+            "        place(): A emits Placed, Placed",
+            "    }",
+            "}",
+            "",
+        ];
+        assert_eq!(format(code), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_format_unions() {
+        let code = "org.a {\n  /// Anything found.\n  union  SearchResult=A|\n    B // found\n  A \
+                    {}\n  B {}\n}\n";
+        let expected = [
+            "org.a {",
+            "    /// Anything found.",
+            "    union SearchResult = A | B // found",
+            "    A { }",
+            "    B { }",
+            "}",
+            "",
+        ];
+        assert_eq!(format(code), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_format_interfaces() {
+        let code = "org.a {\n  interface  Named { name: String }\n  /// A base.\n  abstract \
+                    Base{}\n  A:Base implements Named,B { name: String }\n}\n";
+        let expected = [
+            "org.a {",
+            "    interface Named {",
+            "        name: String",
+            "    }",
+            "    /// A base.",
+            "    abstract Base { }",
+            "    A : Base implements Named, B {",
+            "        name: String",
+            "    }",
+            "}",
+            "",
+        ];
+        assert_eq!(format(code), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_format_generics() {
+        let code = "org.a {\n  Page<T,U> { items: T [*], next(after: T): Page<T, U> }\n  Order { \
+                    pages: Page<Order,Page<Order, Integer>> }\n}\n";
+        let expected = [
+            "org.a {",
+            "    Page<T, U> {",
+            "        items: T [*],",
+            "        next(after: T): Page<T, U>",
+            "    }",
+            "    Order {",
+            "        pages: Page<Order, Page<Order, Integer>>",
+            "    }",
+            "}",
+            "",
+        ];
+        assert_eq!(format(code), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_format_is_idempotent() {
+        let code = include_str!("../test/example.pakken");
+        let formatted = format(code);
+        assert_eq!(format(formatted.as_str()), formatted);
+    }
+
+    #[test]
+    fn test_print_namespace() {
+        let code = "org.a { A : B { c: String [+], d(e: B): B } B { f: Integer? } }";
+        let package = parse(code).expect("Should have parsed");
+        assert_eq!(print(&package.namespaces[0]), format(code));
+    }
+
+    #[test]
+    fn test_format_grammar_error() {
+        assert!(format_code("org.a { A { b String } }", None).is_err());
+    }
+}
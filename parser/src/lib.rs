@@ -2,29 +2,76 @@ extern crate pest;
 extern crate pest_derive;
 
 pub mod dangling;
+pub mod diagnostic;
 pub mod error;
+pub mod format;
+mod loader;
 pub mod parser;
 pub mod pesten;
+pub mod validation;
 
 #[cfg(test)]
 mod tests;
 
+use crate::diagnostic::Diagnostics;
 use crate::error::ParserError;
-use crate::pesten::Parsable;
+use crate::format::format_code;
+use crate::loader::Loader;
 use ast::*;
 use std::fs;
 use std::path::Path;
 
 type ParserResult<T> = std::result::Result<T, ParserError>;
 
-pub fn parse(code: &str) -> ParserResult<Namespace> {
-    Namespace::pest_parse(pesten::Rule::namespace, code)
+/// Parses `code` into a package. Imports are resolved relative to the working directory.
+pub fn parse(code: &str) -> Result<Package, Diagnostics> { without_errors(check(code)) }
+
+/// Parses the file at `file` along with all files it imports into a package.
+pub fn parse_from_file<P: AsRef<Path>>(file: P) -> Result<Package, Diagnostics> {
+    without_errors(check_file(file))
+}
+
+/// Parses and validates `code`, returning every diagnostic including the warnings. The package
+/// is missing if references could not be resolved.
+pub fn check(code: &str) -> (Option<Package>, Diagnostics) {
+    let mut loader = Loader::default();
+    loader.load_code(code, Path::new("."), None);
+    loader.finish()
+}
+
+/// Parses and validates the file at `file` along with all files it imports, returning every
+/// diagnostic including the warnings.
+pub fn check_file<P: AsRef<Path>>(file: P) -> (Option<Package>, Diagnostics) {
+    let mut loader = Loader::default();
+    if let Err(err) = loader.load_file(file.as_ref()) {
+        return (None, Diagnostics::from(err));
+    }
+    loader.finish()
+}
+
+/// Parses and validates `code` as the content of `file`, e.g. an unsaved file of an editor.
+/// Imports are resolved relative to `file`.
+pub fn check_source<P: AsRef<Path>>(code: &str, file: P) -> (Option<Package>, Diagnostics) {
+    let mut loader = Loader::default();
+    loader.load_source(code, file.as_ref());
+    loader.finish()
+}
+
+/// Rewrites `code` in the canonical layout of the DSL, keeping its comments.
+pub fn format(code: &str) -> Result<String, Diagnostics> { format_code(code, None) }
+
+/// Rewrites the file at `file` in the canonical layout of the DSL, keeping its comments.
+pub fn format_file<P: AsRef<Path>>(file: P) -> Result<String, Diagnostics> {
+    let file = file.as_ref();
+    match fs::read_to_string(file) {
+        Ok(code) => format_code(code.as_str(), Some(file)),
+        Err(_) => Err(Diagnostics::from(ParserError::FileNotFound(file.display().to_string()))),
+    }
 }
 
-pub fn parse_from_file<P: AsRef<Path>>(file: P) -> ParserResult<Namespace> {
-    if let Ok(code) = fs::read_to_string(file) {
-        parse(code.as_str())
-    } else {
-        Err(ParserError::FileNotFound)
+fn without_errors(checked: (Option<Package>, Diagnostics)) -> Result<Package, Diagnostics> {
+    match checked {
+        (Some(package), diagnostics) if !diagnostics.has_errors() => Ok(package),
+        (_, diagnostics) => Err(diagnostics),
     }
 }
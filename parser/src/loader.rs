@@ -0,0 +1,153 @@
+use crate::dangling::{check_duplicates, resolve, DanglingFile};
+use crate::diagnostic::{Diagnostic, Diagnostics};
+use crate::error::ParserError;
+use crate::pesten::{pairs, Parsable, Rule};
+use crate::validation::validate;
+use crate::ParserResult;
+use ast::{Identifier, Namespace, Package};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Loads files along with the files they import and assembles them into a package.
+///
+/// Problems are collected as diagnostics instead of aborting, so every error of the model is
+/// reported in one run.
+#[derive(Default)]
+pub(crate) struct Loader {
+    loaded: Vec<(PathBuf, Option<Vec<Identifier>>)>,
+    namespaces: Vec<LoadedNamespace>,
+    diagnostics: Diagnostics,
+}
+
+struct LoadedNamespace {
+    namespace: Namespace,
+    visible: Vec<Identifier>,
+}
+
+impl Loader {
+    /// Loads the file at `path` unless it has already been loaded and returns the identifiers of
+    /// the namespaces it declares, or `None` if the file contains grammar errors.
+    pub fn load_file(&mut self, path: &Path) -> ParserResult<Option<Vec<Identifier>>> {
+        let not_found = || ParserError::FileNotFound(path.display().to_string());
+        let canonical = path.canonicalize().map_err(|_| not_found())?;
+        if let Some((_, identifiers)) = self.loaded.iter().find(|(p, _)| p == &canonical) {
+            return Ok(identifiers.clone());
+        }
+
+        let code = fs::read_to_string(&canonical).map_err(|_| not_found())?;
+        Ok(self.load_registered(canonical, code.as_str(), path))
+    }
+
+    /// Loads `code` as the content of `file`, which may differ from what is saved, e.g. while
+    /// the file is edited.
+    pub fn load_source(&mut self, code: &str, file: &Path) {
+        let canonical = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+        self.load_registered(canonical, code, file);
+    }
+
+    /// Loads `code` of `file`, registering it as loaded under its `canonical` path up front, so
+    /// files importing each other are loaded once and a file importing the edited `file` does
+    /// not load its saved content.
+    fn load_registered(
+        &mut self, canonical: PathBuf, code: &str, file: &Path,
+    ) -> Option<Vec<Identifier>> {
+        self.loaded.push((canonical.clone(), None));
+        let dir = file.parent().unwrap_or_else(|| Path::new(".")).to_path_buf();
+        let identifiers = self.load_code(code, dir.as_path(), Some(file));
+        if let Some(loaded) = self.loaded.iter_mut().find(|(p, _)| p == &canonical) {
+            loaded.1 = identifiers.clone();
+        }
+        identifiers
+    }
+
+    /// Loads `code` of `file`, resolving its imports relative to `dir`.
+    pub fn load_code(
+        &mut self, code: &str, dir: &Path, file: Option<&Path>,
+    ) -> Option<Vec<Identifier>> {
+        self.diagnostics.add_source(file, code);
+        let parsed = match pairs(Rule::pakken, code) {
+            Ok(mut pairs) => match pairs.next() {
+                Some(pair) => DanglingFile::from_pest(pair),
+                None => Err(ParserError::NoToken),
+            },
+            Err(err) => {
+                self.diagnostics.push(Diagnostic::from_pest(err, file));
+                return None;
+            },
+        };
+        match parsed {
+            Ok(dangling) => {
+                let identifiers = Self::identifiers(&dangling);
+                self.load(dangling, dir, file);
+                Some(identifiers)
+            },
+            Err(err) => {
+                self.diagnostics.report(err, file, None);
+                None
+            },
+        }
+    }
+
+    fn load(&mut self, dangling: DanglingFile, dir: &Path, file: Option<&Path>) {
+        // Namespaces of the same file are visible to each other, other namespaces only if
+        // imported.
+        let mut visible = Self::identifiers(&dangling);
+        for import in &dangling.imports {
+            let path = dir.join(import.path.as_str());
+            match self.load_file(path.as_path()) {
+                Ok(Some(declared)) => {
+                    if !declared.contains(&import.namespace) {
+                        let err = ParserError::NamespaceNotFound(
+                            import.namespace.clone(),
+                            path.display().to_string(),
+                        );
+                        self.diagnostics.report(err, file, Some(import.span));
+                    }
+                },
+                Ok(None) => (),
+                Err(err) => self.diagnostics.report(err, file, Some(import.span)),
+            }
+            visible.push(import.namespace.clone());
+        }
+
+        for mut namespace in dangling.namespaces {
+            namespace.source = file.map(Path::to_path_buf);
+            self.namespaces.push(LoadedNamespace { namespace, visible: visible.clone() });
+        }
+    }
+
+    /// Resolves the references of all loaded namespaces and validates the package.
+    ///
+    /// The package is only returned if all references could be resolved.
+    pub fn finish(mut self) -> (Option<Package>, Diagnostics) {
+        let mut package = Package::new();
+        let mut visibilities = vec![];
+        for LoadedNamespace { namespace, visible } in self.namespaces {
+            if package.find_namespace(namespace.identifier.as_str()).is_some() {
+                let file = namespace.source.as_deref();
+                let err = ParserError::NamespaceAlreadyDefined(namespace.identifier.clone());
+                self.diagnostics.report(err, file, Some(namespace.span));
+                continue;
+            }
+            check_duplicates(&namespace, &mut self.diagnostics);
+            visibilities.push(visible);
+            package.add_namespace(namespace);
+        }
+
+        for (namespace, visible) in package.namespaces.iter().zip(visibilities) {
+            let visible: Vec<&Namespace> =
+                visible.iter().filter_map(|i| package.find_namespace(i.as_str())).collect();
+            resolve(namespace, visible.as_slice(), &mut self.diagnostics);
+        }
+
+        if self.diagnostics.has_errors() {
+            return (None, self.diagnostics);
+        }
+        validate(&package, &mut self.diagnostics);
+        (Some(package), self.diagnostics)
+    }
+
+    fn identifiers(file: &DanglingFile) -> Vec<Identifier> {
+        file.namespaces.iter().map(|n| n.identifier.clone()).collect()
+    }
+}
@@ -1,30 +1,68 @@
 use crate::dangling::{
-    DanglingAttribute, DanglingOperation, DanglingParameter, DanglingStructure, Undangle,
+    DanglingAttribute, DanglingFile, DanglingOperation, DanglingParameter, DanglingStructure,
+    Import, Undangle,
 };
 use crate::error::ParserError;
-use crate::pesten::{Parsable, Rule};
+use crate::pesten::{annotations, declaration_span, docs, span_of, Parsable, Rule};
 use crate::ParserResult;
 use ast::Entity::Scalar as EScalar;
-use ast::{Entity, Identifier, Multiplicity, Namespace, Number};
-use ast::{Enum, Scalar};
+use ast::{Constraint, ConstraintKind, CustomScalar, Enum, EnumMember, EnumValue, Scalar};
+use ast::{Entity, Inverse, Key, Multiplicity, Namespace, Number, Ownership, Reference};
+use ast::{OperationKind, Value};
+use ast::{Service, Union};
+use ast::{StructureKind, TypeParameter};
 use pest::iterators::Pair;
 use std::rc::Rc;
 
+impl Parsable for DanglingFile {
+    fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        let mut imports = vec![];
+        let mut namespaces = vec![];
+        for inner_pair in pair.into_inner() {
+            match inner_pair.as_rule() {
+                Rule::import => imports.push(Import::from_pest(inner_pair)?),
+                Rule::namespace => namespaces.push(Namespace::from_pest(inner_pair)?),
+                Rule::EOI => (),
+                other => return Err(ParserError::InvalidRule(other)),
+            }
+        }
+        Ok(DanglingFile { imports, namespaces })
+    }
+}
+
+impl Parsable for Import {
+    fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        let span = span_of(&pair);
+        let mut inner_pairs = pair.into_inner();
+        let namespace = String::from(
+            inner_pairs.next().expect("Import should always have a namespace.").as_str(),
+        );
+        let source = inner_pairs.next().expect("Import should always have a source.");
+        let path = String::from(
+            source.into_inner().next().expect("Import source should have a path.").as_str(),
+        );
+        Ok(Import { namespace, path, span })
+    }
+}
+
 impl Parsable for Namespace {
     fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        let span = span_of(&pair);
         let mut inner_pairs = pair.into_inner();
         let identifier = String::from(
             inner_pairs.next().expect("Namespace should always have an identifier.").as_str(),
         );
 
         let mut namespace = Namespace::new(identifier);
+        namespace.span = span;
 
-        namespace.add_entity(Rc::new(EScalar(Scalar::String)));
-        namespace.add_entity(Rc::new(EScalar(Scalar::Boolean)));
-        namespace.add_entity(Rc::new(EScalar(Scalar::Character)));
-        namespace.add_entity(Rc::new(EScalar(Scalar::Double)));
-        namespace.add_entity(Rc::new(EScalar(Scalar::Integer)));
+        for scalar in Scalar::BUILT_IN.iter() {
+            namespace.add_entity(Rc::new(EScalar(*scalar)));
+        }
+        namespace.add_entity(Rc::new(Entity::Map));
 
+        // Duplicates are reported and references are resolved once all namespaces of the package
+        // are declared.
         for inner_pair in inner_pairs {
             match inner_pair.as_rule() {
                 Rule::entitytype => {
@@ -33,13 +71,25 @@ impl Parsable for Namespace {
                     match entity_type.as_rule() {
                         Rule::entity => {
                             let struc = DanglingStructure::from_pest(entity_type)?;
-                            let entity = Entity::Structure(struc.undangle(&namespace)?);
-                            namespace.add_entity(Rc::new(entity));
+                            namespace.add_entity(Rc::new(Entity::Structure(struc.undangle())));
                         },
                         Rule::enumeration => {
                             let enumeration = Entity::Enum(Enum::from_pest(entity_type)?);
                             namespace.add_entity(Rc::new(enumeration));
                         },
+                        Rule::scalar => {
+                            let scalar =
+                                Entity::CustomScalar(CustomScalar::from_pest(entity_type)?);
+                            namespace.add_entity(Rc::new(scalar));
+                        },
+                        Rule::union => {
+                            let union = Entity::Union(Union::from_pest(entity_type)?);
+                            namespace.add_entity(Rc::new(union));
+                        },
+                        Rule::service => {
+                            let service = Entity::Service(Service::from_pest(entity_type)?);
+                            namespace.add_entity(Rc::new(service));
+                        },
                         other => return Err(ParserError::InvalidRule(other)),
                     }
                 },
@@ -51,21 +101,106 @@ impl Parsable for Namespace {
     }
 }
 
+impl Parsable for CustomScalar {
+    fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        let span = declaration_span(&pair);
+        let mut inner_pairs = pair.into_inner();
+        let docs = docs(&mut inner_pairs);
+        let annotations = annotations(&mut inner_pairs);
+        inner_pairs.next().expect("Scalar must start with its keyword.");
+        let name = String::from(inner_pairs.next().expect("Scalar must have a name.").as_str());
+        let base = Reference::from_pest(inner_pairs.next().expect("Scalar must have a base."))?;
+        Ok(CustomScalar { name, docs, annotations, base, span })
+    }
+}
+
+impl Parsable for Union {
+    fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        let span = declaration_span(&pair);
+        let mut inner_pairs = pair.into_inner();
+        let docs = docs(&mut inner_pairs);
+        let annotations = annotations(&mut inner_pairs);
+        inner_pairs.next().expect("Union must start with its keyword.");
+        let name = String::from(inner_pairs.next().expect("Union must have a name.").as_str());
+        let mut members = vec![];
+        for member in inner_pairs {
+            members.push(Reference::from_pest(member)?);
+        }
+        Ok(Union { name, docs, annotations, members, span })
+    }
+}
+
+impl Parsable for Service {
+    fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        let span = declaration_span(&pair);
+        let mut inner_pairs = pair.into_inner();
+        let docs = docs(&mut inner_pairs);
+        let annotations = annotations(&mut inner_pairs);
+        inner_pairs.next().expect("Service must start with its keyword.");
+        let name = String::from(inner_pairs.next().expect("Service must have a name.").as_str());
+        let mut operations = vec![];
+        for operation in inner_pairs {
+            operations.push(DanglingOperation::from_pest(operation)?.undangle());
+        }
+        Ok(Service { name, docs, annotations, operations, span })
+    }
+}
+
+impl Parsable for Reference {
+    fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        if pair.as_rule() != Rule::typeref {
+            return Ok(Reference::new(String::from(pair.as_str()), span_of(&pair)));
+        }
+        let mut inner_pairs = pair.into_inner();
+        let name = inner_pairs.next().expect("Type should always have a name.");
+        let mut arguments = vec![];
+        if let Some(argument_pairs) = inner_pairs.next() {
+            for argument in argument_pairs.into_inner() {
+                arguments.push(Reference::from_pest(argument)?);
+            }
+        }
+        let reference = Reference::new(String::from(name.as_str()), span_of(&name));
+        Ok(reference.with_arguments(arguments))
+    }
+}
+
 impl Parsable for DanglingStructure {
     fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        let span = declaration_span(&pair);
         let mut inner_pairs = pair.into_inner();
+        let docs = docs(&mut inner_pairs);
+        let annotations = annotations(&mut inner_pairs);
         let mut attributes: Vec<DanglingAttribute> = vec![];
         let mut operations: Vec<DanglingOperation> = vec![];
-        let name = String::from(
-            inner_pairs.next().expect("Entity should always have a parent identifier").as_str(),
-        );
-        let mut parent_identifier: Option<Identifier> = None;
+        let mut kind = StructureKind::Concrete;
+        let mut first = inner_pairs.next().expect("Entity should always have an identifier");
+        if first.as_rule() == Rule::structurekind {
+            kind = match first.as_str() {
+                "interface" => StructureKind::Interface,
+                "event" => StructureKind::Event,
+                _ => StructureKind::Abstract,
+            };
+            first = inner_pairs.next().expect("Entity should always have an identifier");
+        }
+        let name = String::from(first.as_str());
+        let mut parameters = vec![];
+        let mut parent: Option<Reference> = None;
+        let mut interfaces: Vec<Reference> = vec![];
 
         for pair in inner_pairs {
             match pair.as_rule() {
+                Rule::typeparameters => {
+                    for parameter in pair.into_inner() {
+                        let name = String::from(parameter.as_str());
+                        let span = span_of(&parameter);
+                        parameters
+                            .push(Rc::new(Entity::TypeParameter(TypeParameter { name, span })));
+                    }
+                },
                 Rule::parententityname => {
-                    parent_identifier = Some(String::from(pair.as_str()));
+                    parent = Some(Reference::from_pest(pair)?);
                 },
+                Rule::interfacename => interfaces.push(Reference::from_pest(pair)?),
                 Rule::feature => {
                     let inner_feature = pair.into_inner();
                     for feature_pair in inner_feature {
@@ -84,32 +219,100 @@ impl Parsable for DanglingStructure {
             }
         }
 
-        let parsed = DanglingStructure { name, attributes, operations, parent: parent_identifier };
+        let parsed = DanglingStructure {
+            name,
+            docs,
+            annotations,
+            kind,
+            parameters,
+            attributes,
+            operations,
+            parent,
+            interfaces,
+            span,
+        };
         Ok(parsed)
     }
 }
 
 impl Parsable for DanglingAttribute {
     fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        let span = declaration_span(&pair);
         let mut inner_pairs = pair.into_inner();
+        let docs = docs(&mut inner_pairs);
+        let annotations = annotations(&mut inner_pairs);
 
-        let name =
-            String::from(inner_pairs.next().expect("Attribute must always have a name.").as_str());
-        let entity_identifier =
-            String::from(inner_pairs.next().expect("Attribute must always have type.").as_str());
-        let multiplicity = match inner_pairs.next() {
-            Some(pair) => Multiplicity::from_pest(pair)?,
-            None => Multiplicity::Single,
-        };
+        let mut first = inner_pairs.next().expect("Attribute must always have a name.");
+        let mut key = None;
+        let mut ownership = Ownership::Association;
+        if first.as_rule() == Rule::keykind {
+            if first.as_str().starts_with("generated") {
+                key = Some(Key::Generated);
+            } else {
+                key = Some(Key::Provided);
+            }
+            first = inner_pairs.next().expect("Attribute must always have a name.");
+        } else if first.as_rule() == Rule::composition {
+            ownership = Ownership::Composition;
+            first = inner_pairs.next().expect("Attribute must always have a name.");
+        }
+        let name = String::from(first.as_str());
+        let entity =
+            Reference::from_pest(inner_pairs.next().expect("Attribute must always have type."))?;
+        let mut multiplicity = Multiplicity::Single;
+        let mut constraints = vec![];
+        let mut default = None;
+        let mut inverse = None;
+        for pair in inner_pairs {
+            match pair.as_rule() {
+                Rule::multiplicity => multiplicity = Multiplicity::from_pest(pair)?,
+                Rule::inverse => {
+                    let name = pair.into_inner().next().expect("Inverse must have a name.");
+                    inverse =
+                        Some(Inverse { name: String::from(name.as_str()), span: span_of(&name) });
+                },
+                Rule::constraints => {
+                    for constraint in pair.into_inner() {
+                        constraints.push(Constraint::from_pest(constraint)?);
+                    }
+                },
+                _ => default = Some(Value::from_pest(pair)?),
+            }
+        }
 
-        Ok(DanglingAttribute { name, entity: entity_identifier, multiplicity })
+        Ok(DanglingAttribute {
+            name,
+            docs,
+            annotations,
+            entity,
+            multiplicity,
+            key,
+            ownership,
+            inverse,
+            constraints,
+            default,
+            span,
+        })
     }
 }
 
 impl Parsable for DanglingOperation {
     fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        let span = declaration_span(&pair);
         let mut inner_pairs = pair.into_inner();
-        let name = String::from(inner_pairs.next().expect("Operation must have a name.").as_str());
+        let docs = docs(&mut inner_pairs);
+        let annotations = annotations(&mut inner_pairs);
+        let mut first = inner_pairs.next().expect("Operation must have a name.");
+        let mut kind = None;
+        if first.as_rule() == Rule::operationkind {
+            if first.as_str() == "query" {
+                kind = Some(OperationKind::Query);
+            } else {
+                kind = Some(OperationKind::Command);
+            }
+            first = inner_pairs.next().expect("Operation must have a name.");
+        }
+        let name = String::from(first.as_str());
         let mut parameter = vec![];
 
         let next = inner_pairs.next().unwrap();
@@ -118,27 +321,89 @@ impl Parsable for DanglingOperation {
                 parameter.push(DanglingParameter::from_pest(parameter_pair)?);
             }
         }
-        let returns_identifier: Option<String>;
-        if let Some(returns) = inner_pairs.next() {
-            returns_identifier = Some(returns.as_str().to_string());
-        } else {
-            returns_identifier = None;
+        let mut returns = None;
+        let mut emits = vec![];
+        for pair in inner_pairs {
+            match pair.as_rule() {
+                Rule::emits => {
+                    for event in pair.into_inner() {
+                        emits.push(Reference::from_pest(event)?);
+                    }
+                },
+                _ => returns = Some(Reference::from_pest(pair)?),
+            }
         }
 
-        Ok(DanglingOperation { name, parameter, returns: returns_identifier })
+        Ok(DanglingOperation { name, docs, annotations, parameter, returns, kind, emits, span })
     }
 }
 
 impl Parsable for DanglingParameter {
     fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        let span = span_of(&pair);
         let mut inner_pairs = pair.into_inner();
         let name =
             String::from(inner_pairs.next().expect("Parameter should have a name.").as_str());
-        let entity_identifier = String::from(
-            inner_pairs.next().expect("Parameter should have a return type.").as_str(),
-        );
+        let entity = Reference::from_pest(
+            inner_pairs.next().expect("Parameter should have a return type."),
+        )?;
+
+        Ok(DanglingParameter { name, entity, span })
+    }
+}
+
+impl Parsable for Constraint {
+    fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        let span = span_of(&pair);
+        let mut inner_pairs = pair.into_inner();
+        let kind = match inner_pairs.next().expect("Constraint must have a kind.").as_str() {
+            "min" => ConstraintKind::Min,
+            "max" => ConstraintKind::Max,
+            "minLength" => ConstraintKind::MinLength,
+            "maxLength" => ConstraintKind::MaxLength,
+            "pattern" => ConstraintKind::Pattern,
+            _ => return Err(ParserError::Unhandled),
+        };
+        let value = Value::from_pest(inner_pairs.next().expect("Constraint must have a value."))?;
+        Ok(Constraint { kind, value, span })
+    }
+}
 
-        Ok(DanglingParameter { name, entity: entity_identifier })
+impl Parsable for Value {
+    fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        let literal = pair.into_inner().next().expect("Value must have a literal.");
+        let text = literal.as_str();
+        match literal.as_rule() {
+            Rule::string => Ok(Value::String(literal.into_inner().as_str().to_owned())),
+            Rule::integer => match text.parse() {
+                Ok(integer) => Ok(Value::Integer(integer)),
+                Err(_) => Err(ParserError::InvalidNumber(text.to_owned())),
+            },
+            // Literals beyond the range of doubles would become infinite.
+            Rule::double => match text.parse::<f64>() {
+                Ok(double) if double.is_finite() => Ok(Value::Double(double)),
+                _ => Err(ParserError::InvalidNumber(text.to_owned())),
+            },
+            Rule::boolean => Ok(Value::Boolean(text == "true")),
+            Rule::emptylist => Ok(Value::EmptyList),
+            Rule::membername => Ok(Value::Member(text.to_owned())),
+            _ => Err(ParserError::Unhandled),
+        }
+    }
+}
+
+impl Parsable for EnumValue {
+    fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        let literal = pair.into_inner().next().expect("Enum value must have a literal.");
+        let text = literal.as_str();
+        match literal.as_rule() {
+            Rule::string => Ok(EnumValue::String(literal.into_inner().as_str().to_owned())),
+            Rule::integer => match text.parse() {
+                Ok(integer) => Ok(EnumValue::Integer(integer)),
+                Err(_) => Err(ParserError::InvalidNumber(text.to_owned())),
+            },
+            _ => Err(ParserError::Unhandled),
+        }
     }
 }
 
@@ -197,17 +462,38 @@ impl Parsable for Multiplicity {
 
 impl Parsable for Enum {
     fn from_pest(pair: Pair<Rule>) -> ParserResult<Self> {
+        let span = declaration_span(&pair);
         let mut inner_pairs = pair.into_inner();
+        let enum_docs = docs(&mut inner_pairs);
+        let enum_annotations = annotations(&mut inner_pairs);
         let name = inner_pairs.next().expect("Enumeration should have a name.").as_str();
         let mut members = vec![];
         for pair in inner_pairs {
+            let member_span = declaration_span(&pair);
             let mut member_pairs = pair.into_inner();
+            let member_docs = docs(&mut member_pairs);
+            let member_annotations = annotations(&mut member_pairs);
             let member_name =
                 member_pairs.next().expect("Enumeration member should have a name.").as_str();
-            let member_value = member_pairs.next().map(|i| i.as_str().parse::<usize>().unwrap());
-            members.push((member_name.to_string(), member_value));
+            let member_value = match member_pairs.next() {
+                Some(value) => Some(EnumValue::from_pest(value)?),
+                None => None,
+            };
+            members.push(EnumMember {
+                name: member_name.to_string(),
+                docs: member_docs,
+                annotations: member_annotations,
+                value: member_value,
+                span: member_span,
+            });
         }
-        Ok(Enum { identifier: name.to_string(), values: members })
+        Ok(Enum {
+            identifier: name.to_string(),
+            docs: enum_docs,
+            annotations: enum_annotations,
+            members,
+            span,
+        })
     }
 }
 
@@ -215,13 +501,21 @@ impl Parsable for Enum {
 mod tests {
     use crate::dangling::DanglingStructure;
     use crate::error::ParserError;
+    use crate::parse;
     use crate::pesten::{PakkenRule, Parsable};
-    use ast::Namespace;
+    use ast::{
+        Annotated, ConstraintKind, Entity, Identifying, Namespace, OperationKind, Scalar, Span,
+        StructureKind, Value,
+    };
+
+    fn errors(code: &str) -> Vec<ParserError> {
+        parse(code).expect_err("Should not have parsed").into_iter().map(|d| d.error).collect()
+    }
 
     #[test]
     fn parse_namespace_ok() {
         let code = "namespace.is.lit {}";
-        let expected = Namespace { identifier: String::from("namespace.is.lit"), entities: vec![] };
+        let expected = Namespace::new(String::from("namespace.is.lit"));
         let parsed =
             Namespace::pest_parse(PakkenRule::namespace, code).expect("Should have parsed");
         assert_eq!(parsed.identifier, expected.identifier);
@@ -243,12 +537,320 @@ mod tests {
         let code = "Name { attribute: String, operation() }";
         let expected = DanglingStructure {
             name: "Name".to_string(),
+            docs: None,
+            annotations: vec![],
+            kind: StructureKind::Concrete,
+            parameters: vec![],
             parent: None,
+            interfaces: vec![],
             attributes: vec![],
             operations: vec![],
+            span: Span::default(),
         };
 
         let parsed = DanglingStructure::pest_parse(PakkenRule::entitytype, code)
             .expect("Should have parsed");
     }
+
+    #[test]
+    fn parse_forward_reference() {
+        let code = "forward { Person { car: Car } Car { brand: String } }";
+        let parsed = parse(code).expect("Should have parsed");
+        let person = parsed.find_entity("forward.Person").expect("Person should exist");
+        if let Entity::Structure(struc) = person.as_ref() {
+            assert_eq!(struc.attributes[0].entity.entity().identifier(), "Car");
+        } else {
+            panic!("Person should be a structure");
+        }
+    }
+
+    #[test]
+    fn parse_mutual_reference() {
+        let code = "shop { Order { customer: Customer } Customer { orders: Order [*] } }";
+        let parsed = parse(code).expect("Should have parsed");
+        let order = parsed.find_entity("shop.Order").expect("Order should exist");
+        if let Entity::Structure(struc) = order.as_ref() {
+            let customer = struc.attributes[0].entity.entity();
+            if let Entity::Structure(customer) = customer.as_ref() {
+                assert_eq!(customer.attributes[0].entity.entity().identifier(), "Order");
+            } else {
+                panic!("Customer should be a structure");
+            }
+        } else {
+            panic!("Order should be a structure");
+        }
+    }
+
+    #[test]
+    fn parse_unknown_entity() {
+        let code = "unknown { Person { car: Car } }";
+        if let ParserError::InvalidEntity(name) = &errors(code)[0] {
+            assert_eq!(name, "Car");
+        } else {
+            panic!("Should not resolve an undeclared entity");
+        }
+    }
+
+    #[test]
+    fn parse_duplicate_entity() {
+        let code = "duplicate { Person { } Person { } }";
+        if let ParserError::EntityAlreadyDefined(name) = &errors(code)[0] {
+            assert_eq!(name, "Person");
+        } else {
+            panic!("Should not allow two entities with the same name");
+        }
+    }
+
+    #[test]
+    fn parse_multiple_namespaces() {
+        let code = "org.a { A { b: org.b.B } } org.b { B { a: org.a.A } }";
+        let parsed = parse(code).expect("Should have parsed");
+        assert_eq!(parsed.namespaces.len(), 2);
+        assert!(parsed.find_entity("org.b.B").is_some());
+    }
+
+    #[test]
+    fn parse_duplicate_namespace() {
+        let code = "org.a { } org.a { }";
+        if let ParserError::NamespaceAlreadyDefined(name) = &errors(code)[0] {
+            assert_eq!(name, "org.a");
+        } else {
+            panic!("Should not allow two namespaces with the same identifier");
+        }
+    }
+
+    #[test]
+    fn parse_collects_all_errors() {
+        let code = "org.a {\n    A { b: B, c: C }\n    A { }\n}";
+        let diagnostics: Vec<_> =
+            parse(code).expect_err("Should not have parsed").into_iter().collect();
+        assert_eq!(diagnostics.len(), 3);
+        let span = diagnostics[0].span.expect("Should have a location");
+        assert_eq!((span.line, span.column), (3, 5));
+        assert_eq!(&code[span.start .. span.end], "A { }");
+        let span = diagnostics[2].span.expect("Should have a location");
+        assert_eq!((span.line, span.column), (2, 18));
+        assert_eq!(&code[span.start .. span.end], "C");
+    }
+
+    #[test]
+    fn parse_spans() {
+        let code =
+            "org.a {\n    A {\n        b: String,\n        c(d: String)\n    }\n    E { f = 1 }\n}";
+        let parsed = parse(code).expect("Should have parsed");
+        let namespace = &parsed.namespaces[0];
+        assert_eq!((namespace.span.line, namespace.span.column), (1, 1));
+        if let Entity::Structure(struc) = parsed.find_entity("org.a.A").unwrap().as_ref() {
+            let attribute = &struc.attributes[0];
+            assert_eq!(&code[attribute.span.start .. attribute.span.end], "b: String");
+            assert_eq!((attribute.span.line, attribute.span.column), (3, 9));
+            let operation = &struc.operations[0];
+            assert_eq!(&code[operation.span.start .. operation.span.end], "c(d: String)");
+            let parameter = &operation.parameter[0];
+            assert_eq!(&code[parameter.span.start .. parameter.span.end], "d: String");
+        } else {
+            panic!("A should be a structure");
+        }
+        if let Entity::Enum(enumeration) = parsed.find_entity("org.a.E").unwrap().as_ref() {
+            let member = &enumeration.members[0];
+            assert_eq!((member.span.line, member.span.column), (6, 9));
+        } else {
+            panic!("E should be an enum");
+        }
+    }
+
+    #[test]
+    fn parse_docs() {
+        let code = "org.a {\n    /// A thing.\n    /// Second line.\n    A {\n        /// The \
+                    b.\n        b: String,\n        // not a doc\n        c()\n    }\n    /// \
+                    Colors\n    E { /// Red\n red }\n}";
+        let parsed = parse(code).expect("Should have parsed");
+        if let Entity::Structure(struc) = parsed.find_entity("org.a.A").unwrap().as_ref() {
+            assert_eq!(struc.docs.as_deref(), Some("A thing.\nSecond line."));
+            assert_eq!((struc.span.line, struc.span.column), (4, 5));
+            assert_eq!(struc.attributes[0].docs.as_deref(), Some("The b."));
+            assert_eq!(
+                &code[struc.attributes[0].span.start .. struc.attributes[0].span.end],
+                "b: String"
+            );
+            assert!(struc.operations[0].docs.is_none());
+        } else {
+            panic!("A should be a structure");
+        }
+        if let Entity::Enum(enumeration) = parsed.find_entity("org.a.E").unwrap().as_ref() {
+            assert_eq!(enumeration.docs.as_deref(), Some("Colors"));
+            assert_eq!(enumeration.members[0].docs.as_deref(), Some("Red"));
+        } else {
+            panic!("E should be an enum");
+        }
+    }
+
+    #[test]
+    fn parse_annotations() {
+        let code = "org.a {\n    /// A thing.\n    @graphql(skip) @deprecated(\"use B\")\n    A \
+                    {\n        @id @unique\n        b: String,\n        @readonly c()\n    }\n    \
+                    E { @old red }\n}";
+        let parsed = parse(code).expect("Should have parsed");
+        if let Entity::Structure(struc) = parsed.find_entity("org.a.A").unwrap().as_ref() {
+            assert_eq!(struc.docs.as_deref(), Some("A thing."));
+            assert!(struc.has_hint("graphql", "skip"));
+            let deprecated = struc.annotation("deprecated").expect("Should be deprecated");
+            assert_eq!(deprecated.value.as_deref(), Some("use B"));
+            assert_eq!(
+                &code[deprecated.span.start .. deprecated.span.end],
+                "@deprecated(\"use B\")"
+            );
+            assert_eq!((struc.span.line, struc.span.column), (4, 5));
+            let names: Vec<&str> =
+                struc.attributes[0].annotations.iter().map(|a| a.name.as_str()).collect();
+            assert_eq!(names, vec!["id", "unique"]);
+            assert!(struc.attributes[0].annotation("id").unwrap().value.is_none());
+            assert!(struc.operations[0].is_annotated("readonly"));
+        } else {
+            panic!("A should be a structure");
+        }
+        if let Entity::Enum(enumeration) = parsed.find_entity("org.a.E").unwrap().as_ref() {
+            assert!(enumeration.members[0].is_annotated("old"));
+        } else {
+            panic!("E should be an enum");
+        }
+        assert!(parse("org.a { A { @ b: String } }").is_err());
+    }
+
+    #[test]
+    fn parse_defaults() {
+        let code = "org.a { S { red } A { a: Boolean = false, b: Integer? = -3, c: Double = 0.5, \
+                    d: String = \"x y\", e: S = red, f: String [*] = [], g: String } }";
+        let parsed = parse(code).expect("Should have parsed");
+        if let Entity::Structure(struc) = parsed.find_entity("org.a.A").unwrap().as_ref() {
+            let defaults: Vec<Option<Value>> =
+                struc.attributes.iter().map(|a| a.default.clone()).collect();
+            assert_eq!(defaults, vec![
+                Some(Value::Boolean(false)),
+                Some(Value::Integer(-3)),
+                Some(Value::Double(0.5)),
+                Some(Value::String(String::from("x y"))),
+                Some(Value::Member(String::from("red"))),
+                Some(Value::EmptyList),
+                None,
+            ]);
+            assert_eq!(
+                &code[struc.attributes[1].span.start .. struc.attributes[1].span.end],
+                "b: Integer? = -3"
+            );
+        } else {
+            panic!("A should be a structure");
+        }
+    }
+
+    #[test]
+    fn parse_infinite_double() {
+        let code = format!("org.a {{ A {{ a: Double = 1{}.0 }} }}", "0".repeat(400));
+        let errors = parse(code.as_str()).expect_err("Should not parse an infinite double");
+        let error = &errors.iter().next().unwrap().error;
+        assert!(matches!(error, ParserError::InvalidNumber(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn parse_constraints() {
+        let code = "org.a { A { age: Integer (min 0, max 150) = 18, b: String (minimum 1) } }";
+        let errors = parse(code).expect_err("Should not know `minimum`");
+        let message = errors.iter().next().unwrap().render(None);
+        assert!(message.contains("a constraint"), "{}", message);
+
+        let code = "org.a { A { age: Integer? (min 0, max 150) = 18, e: String (pattern \"@\") } }";
+        let parsed = parse(code).expect("Should have parsed");
+        if let Entity::Structure(struc) = parsed.find_entity("org.a.A").unwrap().as_ref() {
+            let age = &struc.attributes[0];
+            assert_eq!(age.constraints.len(), 2);
+            assert_eq!(age.constraints[1].kind, ConstraintKind::Max);
+            assert_eq!(age.constraints[1].value, Value::Integer(150));
+            assert_eq!(
+                &code[age.constraints[1].span.start .. age.constraints[1].span.end],
+                "max 150"
+            );
+            assert_eq!(age.default, Some(Value::Integer(18)));
+            let pattern = &struc.attributes[1].constraints[0];
+            assert_eq!(pattern.value, Value::String(String::from("@")));
+        } else {
+            panic!("A should be a structure");
+        }
+    }
+
+    #[test]
+    fn parse_scalars() {
+        let code = "org.a {\n    /// An address.\n    @unique scalar Email : String\n    A { b: \
+                    Email, c: Date, d: DateTime, e: UUID, f: Decimal }\n}";
+        let parsed = parse(code).expect("Should have parsed");
+        let email = parsed.find_entity("org.a.Email").expect("Email should exist");
+        if let Entity::CustomScalar(custom) = email.as_ref() {
+            assert_eq!(custom.docs.as_deref(), Some("An address."));
+            assert!(custom.is_annotated("unique"));
+            assert_eq!(&code[custom.span.start .. custom.span.end], "scalar Email : String");
+        } else {
+            panic!("Email should be a scalar");
+        }
+        assert_eq!(email.scalar(), Some(Scalar::String));
+        assert_eq!(parsed.find_entity("org.a.UUID").unwrap().scalar(), Some(Scalar::Uuid));
+    }
+
+    #[test]
+    fn parse_unions() {
+        let code = "org.a {\n    /// Anything found.\n    union Found = A | org.b.B // all\n    A \
+                    { a: String }\n}\norg.b {\n    B { b: String }\n}";
+        let parsed = parse(code).expect("Should have parsed");
+        let found = parsed.find_entity("org.a.Found").expect("Found should exist");
+        if let Entity::Union(union) = found.as_ref() {
+            assert_eq!(union.docs.as_deref(), Some("Anything found."));
+            assert_eq!(&code[union.span.start .. union.span.end], "union Found = A | org.b.B");
+            let members: Vec<String> =
+                union.members.iter().map(|m| m.entity().identifier()).collect();
+            assert_eq!(members, vec!["A", "B"]);
+        } else {
+            panic!("Found should be a union");
+        }
+    }
+
+    #[test]
+    fn parse_services() {
+        let code = "org.a {\n    /// Handles orders.\n    service Orders {\n        query \
+                    find(number: String): A,\n        command place(a: A): A,\n        \
+                    cancel(number: String)\n    }\n    A { query: String }\n}";
+        let parsed = parse(code).expect("Should have parsed");
+        let orders = parsed.find_entity("org.a.Orders").expect("Orders should exist");
+        if let Entity::Service(service) = orders.as_ref() {
+            assert_eq!(service.docs.as_deref(), Some("Handles orders."));
+            let kinds: Vec<Option<OperationKind>> =
+                service.operations.iter().map(|op| op.kind).collect();
+            assert_eq!(kinds, vec![Some(OperationKind::Query), Some(OperationKind::Command), None]);
+            assert!(service.operations[0].is_query());
+            assert_eq!(service.references().len(), 5);
+        } else {
+            panic!("Orders should be a service");
+        }
+        // Attributes may still be named like the marker.
+        let a = parsed.find_entity("org.a.A").expect("A should exist");
+        assert_eq!(a.identifier(), "A");
+    }
+
+    #[test]
+    fn parse_events() {
+        let code = "org.a {\n    event Placed { a: A }\n    event Cancelled { at: DateTime }\n    \
+                    A {\n        place(): A emits Placed, Cancelled,\n        cancel() emits \
+                    Cancelled, Note: String\n    }\n}";
+        let parsed = parse(code).expect("Should have parsed");
+        let placed = parsed.find_entity("org.a.Placed").expect("Placed should exist");
+        assert!(matches!(placed.as_ref(), Entity::Structure(s) if s.is_event()));
+        let a = parsed.find_entity("org.a.A").expect("A should exist");
+        if let Entity::Structure(struc) = a.as_ref() {
+            let emits = |index: usize| -> Vec<String> {
+                struc.operations[index].emits.iter().map(|e| e.entity().identifier()).collect()
+            };
+            assert_eq!(emits(0), vec!["Placed", "Cancelled"]);
+            assert_eq!(emits(1), vec!["Cancelled"]);
+            assert_eq!(struc.attributes[0].name, "Note");
+        } else {
+            panic!("A should be a structure");
+        }
+    }
 }
@@ -1,6 +1,8 @@
 use crate::error::ParserError;
 use crate::ParserResult;
-use pest::iterators::Pair;
+use ast::{Annotation, Span};
+use pest::error::Error as PestError;
+use pest::iterators::{Pair, Pairs};
 use pest::Parser;
 use pest_derive::*;
 
@@ -23,3 +25,84 @@ pub fn lex(code: &str) -> ParserResult<()> {
     PakkenParser::parse(Rule::pakken, code)?;
     Ok(())
 }
+
+/// Runs the grammar on `code` while keeping pest's error, which knows the location of the problem.
+pub fn pairs(rule: Rule, code: &str) -> Result<Pairs<'_, Rule>, PestError<Rule>> {
+    PakkenParser::parse(rule, code)
+}
+
+/// Returns the location of `pair` in the source.
+pub fn span_of(pair: &Pair<Rule>) -> Span {
+    let pest_span = pair.as_span();
+    let (line, column) = pest_span.start_pos().line_col();
+    // Pairs ending with an optional part include the whitespace and comments skipped before it.
+    let mut text = pair.as_str().trim_end();
+    while let Some(comment) = trailing_comment(text) {
+        text = text[.. comment].trim_end();
+    }
+    let end = pest_span.start() + text.len();
+    Span { start: pest_span.start(), end, line, column }
+}
+
+/// Returns where the `//` comment ending `text` starts, ignoring `//` in strings.
+fn trailing_comment(text: &str) -> Option<usize> {
+    let line_start = text.rfind('\n').map_or(0, |i| i + 1);
+    let mut quoted = false;
+    let mut previous = ' ';
+    for (index, c) in text[line_start ..].char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '/' if previous == '/' && !quoted => {
+                let start = line_start + index - 1;
+                return Some(start).filter(|&s| !text[s ..].starts_with("///"));
+            },
+            _ => (),
+        }
+        previous = c;
+    }
+    None
+}
+
+/// Returns the location of a declaration without the `///` comments and annotations in front of
+/// it, so it starts at its name.
+pub fn declaration_span(pair: &Pair<Rule>) -> Span {
+    let span = span_of(pair);
+    let name =
+        pair.clone().into_inner().find(|p| !matches!(p.as_rule(), Rule::doc | Rule::annotation));
+    match name {
+        Some(first) => Span { end: span.end, ..span_of(&first) },
+        None => span,
+    }
+}
+
+/// Takes the `///` comments in front of a declaration off `pairs` and joins their lines.
+pub fn docs(pairs: &mut Pairs<'_, Rule>) -> Option<String> {
+    let mut lines = vec![];
+    while let Some(pair) = pairs.peek().filter(|p| p.as_rule() == Rule::doc) {
+        pairs.next();
+        let text = pair.as_str()["///".len() ..].trim_end();
+        lines.push(text.strip_prefix(' ').unwrap_or(text).to_owned());
+    }
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+/// Takes the annotations in front of a declaration off `pairs`.
+pub fn annotations(pairs: &mut Pairs<'_, Rule>) -> Vec<Annotation> {
+    let mut annotations = vec![];
+    while let Some(pair) = pairs.peek().filter(|p| p.as_rule() == Rule::annotation) {
+        pairs.next();
+        let span = span_of(&pair);
+        let mut inner = pair.into_inner();
+        let name = inner.next().expect("Annotation should have a name.").as_str().to_owned();
+        let value = inner.next().map(|value| match value.as_rule() {
+            Rule::string => value.into_inner().as_str().to_owned(),
+            _ => value.as_str().to_owned(),
+        });
+        annotations.push(Annotation { name, value, span });
+    }
+    annotations
+}
@@ -1,7 +1,12 @@
-use crate::parse;
+use crate::error::ParserError;
+use crate::format::print_attribute;
 use crate::pesten::lex;
+use crate::{parse, parse_from_file};
+use ast::Entity::Structure;
+use ast::{Cardinality, Identifying};
 use std::fs;
 use std::path::Path;
+use std::rc::Rc;
 
 #[test]
 /// ensures that the grammar is correct
@@ -23,8 +28,8 @@ fn test_parser() {
     let file = fs::read_to_string(path.canonicalize().unwrap());
     if let Ok(code) = file {
         match parse(code.as_str()) {
-            Ok(namespace) => {
-                assert_eq!(namespace.identifier, "org.mobile");
+            Ok(package) => {
+                assert_eq!(package.namespaces[0].identifier, "org.mobile");
             },
             Err(e) => {
                 eprintln!("{}", e.to_string());
@@ -35,3 +40,108 @@ fn test_parser() {
         panic!();
     }
 }
+
+#[test]
+/// ensures that imported files are loaded and qualified names are resolved across namespaces
+fn test_imports() {
+    let package = parse_from_file(Path::new("./test/shop.pakken")).expect("Should have parsed");
+    assert_eq!(package.namespaces.len(), 3);
+    assert!(package.find_namespace("org.billing").is_some());
+
+    let order = package.find_entity("org.shop.Order").expect("Order should exist");
+    if let Structure(struc) = order.as_ref() {
+        let invoice = struc.attributes[1].entity.entity();
+        assert!(Rc::ptr_eq(&invoice, &package.find_entity("org.billing.Invoice").unwrap()));
+    } else {
+        panic!("Order should be a structure");
+    }
+}
+
+#[test]
+/// ensures that files importing each other are loaded once
+fn test_cyclic_imports() {
+    let package = parse_from_file(Path::new("./test/people.pakken")).expect("Should have parsed");
+    assert_eq!(package.namespaces.len(), 2);
+    let pet = package.find_entity("org.pets.Pet").expect("Pet should exist");
+    if let Structure(struc) = pet.as_ref() {
+        let person = struc.attributes[0].entity.entity();
+        assert!(Rc::ptr_eq(&person, &package.find_entity("org.people.Person").unwrap()));
+    } else {
+        panic!("Pet should be a structure");
+    }
+}
+
+#[test]
+/// ensures that namespaces of other files are only visible when imported
+fn test_missing_import() {
+    let code = "org.shop { Order { invoice: org.billing.Invoice } }";
+    let diagnostics = parse(code).expect_err("Should not have parsed");
+    match &diagnostics.iter().next().unwrap().error {
+        ParserError::InvalidEntity(name) => assert_eq!(name, "org.billing.Invoice"),
+        _ => panic!("Should not resolve an entity of a namespace that is not imported"),
+    }
+}
+
+#[test]
+/// ensures that problems of imports are reported along with the other problems of the model
+fn test_import_diagnostics() {
+    let code = "import org.missing from \"./test/missing.pakken\"\nimport org.unknown from \
+                \"./test/billing.pakken\"\norg.shop { Order { invoice: Invoice } }";
+    let diagnostics: Vec<_> =
+        parse(code).expect_err("Should not have parsed").into_iter().collect();
+    assert_eq!(diagnostics.len(), 3);
+    assert!(matches!(diagnostics[0].error, ParserError::FileNotFound(_)));
+    assert_eq!(diagnostics[0].span.unwrap().line, 1);
+    assert!(matches!(diagnostics[1].error, ParserError::NamespaceNotFound(_, _)));
+    assert_eq!(diagnostics[1].span.unwrap().line, 2);
+    assert!(matches!(diagnostics[2].error, ParserError::InvalidEntity(_)));
+    assert_eq!(diagnostics[2].span.unwrap().line, 3);
+}
+
+#[test]
+/// ensures that structures yield inherited features, with overrides in place of the inherited ones
+fn test_effective_features() {
+    let code = "org.a { interface Named { name: String, rename(name: String) } Vehicle implements \
+                Named { name: String, tires: Tire [+], rename(name: String), rename(name: \
+                Integer) } Bike : Vehicle { tires: Tire [2, 4], ring(), rename(name: String) } \
+                Tire { size: Integer } }";
+    let package = parse(code).expect("Should have parsed");
+    let bike = package.find_entity("org.a.Bike").expect("Bike should exist");
+    let bike = match bike.as_ref() {
+        Structure(bike) => bike,
+        _ => panic!("Bike should be a structure"),
+    };
+    let attributes: Vec<String> = bike.effective_attributes().iter().map(print_attribute).collect();
+    assert_eq!(attributes, vec!["name: String", "tires: Tire [2, 4]"]);
+    let operations: Vec<String> =
+        bike.effective_operations().iter().map(|o| o.signature()).collect();
+    assert_eq!(operations, vec!["rename(String)", "rename(Integer)", "ring()"]);
+    assert_eq!(bike.effective_operations()[0].span, bike.operations[1].span);
+    assert_eq!(bike.ancestors().len(), 1);
+    assert_eq!(bike.implemented()[0].identifier(), "Named");
+}
+
+#[test]
+fn test_relationships() {
+    let code = "org.a { Customer { orders: Order [*] inverse customer, favourite: Order? } Order \
+                { customer: Customer, contains lines: Line [+], tags: Tag [*] } Line { size: \
+                Integer } Tag { orders: Order [*] inverse tags } }";
+    let package = parse(code).expect("Should have parsed");
+    let structure = |name: &str| match package.find_entity(name).unwrap().as_ref() {
+        Structure(struc) => struc.clone(),
+        _ => panic!("{} should be a structure", name),
+    };
+    let (customer, order) = (structure("org.a.Customer"), structure("org.a.Order"));
+    let cardinality = |struc: &ast::Structure, index: usize| {
+        struc.attributes[index].cardinality(struc).expect("Should be a relationship")
+    };
+    assert_eq!(cardinality(&customer, 0), Cardinality::OneToMany);
+    assert_eq!(cardinality(&customer, 1), Cardinality::ManyToOne);
+    assert_eq!(cardinality(&order, 0), Cardinality::ManyToOne);
+    assert_eq!(cardinality(&order, 1), Cardinality::OneToMany);
+    assert_eq!(cardinality(&order, 2), Cardinality::ManyToMany);
+    let inverse = order.attributes[0].inverse_attribute(&order).expect("Should have an inverse");
+    assert_eq!(inverse.name, "orders");
+    assert_eq!(print_attribute(&order.attributes[1]), "contains lines: Line [+]");
+    assert_eq!(print_attribute(&customer.attributes[0]), "orders: Order [*] inverse customer");
+}
@@ -0,0 +1,1073 @@
+use crate::diagnostic::{Diagnostic, Diagnostics, Severity};
+use crate::error::ParserError;
+use ast::Multiplicity::{Concrete, UnderUpper};
+use ast::Number::Discrete;
+use ast::{
+    Annotated, Annotation, Attribute, Constraint, ConstraintKind, CustomScalar, Entity, Enum,
+    EnumMember, EnumValue, Identifying, Key, Multiplicity, Namespace, Operation, Ownership,
+    Package, Reference, Scalar, Service, Span, Structure, StructureKind, Union, Value,
+};
+use std::path::Path;
+use std::rc::Rc;
+
+/// Checks the rules a model has to follow beyond the grammar, once all references are resolved.
+pub fn validate(package: &Package, diagnostics: &mut Diagnostics) {
+    for namespace in &package.namespaces {
+        let mut validator = Validator { namespace, diagnostics: &mut *diagnostics };
+        for entity in &namespace.entities {
+            match entity.as_ref() {
+                Entity::Structure(struc) => validator.structure(entity, struc),
+                Entity::Enum(enumeration) => validator.enumeration(enumeration),
+                Entity::CustomScalar(custom) => validator.scalar(custom),
+                Entity::Union(union) => validator.union(union),
+                Entity::Service(service) => validator.service(service),
+                Entity::Scalar(_) | Entity::TypeParameter(_) | Entity::Map => (),
+            }
+        }
+    }
+}
+
+/// Warns about the annotations of `package` which `target` does not understand. Annotations
+/// named after one of the installed `targets`, like `@graphql(skip)`, are meant for that target
+/// and are left alone.
+pub fn check_annotations(
+    package: &Package, target: &str, understood: &[&str], targets: &[String],
+    diagnostics: &mut Diagnostics,
+) {
+    for namespace in &package.namespaces {
+        let mut validator = Validator { namespace, diagnostics: &mut *diagnostics };
+        for entity in &namespace.entities {
+            let mut annotations: Vec<&Annotation> = entity.annotations().iter().collect();
+            match entity.as_ref() {
+                Entity::Structure(struc) => {
+                    let attributes = struc.attributes.iter().flat_map(|a| a.annotations.iter());
+                    annotations.extend(attributes);
+                    annotations.extend(struc.operations.iter().flat_map(|o| o.annotations.iter()));
+                },
+                Entity::Enum(enumeration) => annotations
+                    .extend(enumeration.members.iter().flat_map(|m| m.annotations.iter())),
+                Entity::Service(service) => {
+                    annotations.extend(service.operations.iter().flat_map(|o| o.annotations.iter()))
+                },
+                Entity::Scalar(_)
+                | Entity::CustomScalar(_)
+                | Entity::Union(_)
+                | Entity::TypeParameter(_)
+                | Entity::Map => (),
+            }
+            for annotation in annotations {
+                let name = annotation.name.as_str();
+                if !understood.contains(&name) && !targets.iter().any(|t| t == name) {
+                    let err = ParserError::UnknownAnnotation(name.to_owned(), target.to_owned());
+                    validator.report(Severity::Warning, err, annotation.span);
+                }
+            }
+        }
+    }
+}
+
+struct Validator<'a> {
+    namespace: &'a Namespace,
+    diagnostics: &'a mut Diagnostics,
+}
+
+impl<'a> Validator<'a> {
+    fn report(&mut self, severity: Severity, error: ParserError, span: Span) {
+        let file: Option<&Path> = self.namespace.source.as_deref();
+        let diagnostic = Diagnostic::new(error, file, Some(span)).with_severity(severity);
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn structure(&mut self, entity: &Rc<Entity>, struc: &Structure) {
+        for (index, attr) in struc.attributes.iter().enumerate() {
+            if struc.attributes[.. index].iter().any(|a| a.name == attr.name) {
+                let err = ParserError::DuplicateFeature(struc.name.clone(), attr.name.clone());
+                self.report(Severity::Error, err, attr.span);
+            }
+            if let UnderUpper(Discrete(lower), Discrete(upper)) = attr.multiplicity {
+                if lower > upper {
+                    let err = ParserError::InvalidMultiplicity(lower, upper);
+                    self.report(Severity::Error, err, attr.span);
+                }
+            }
+            self.constraints(attr);
+            self.relationship(struc, attr);
+            match &attr.default {
+                Some(default) if !accepts(attr, default) => {
+                    let err = ParserError::InvalidDefault(
+                        attr.name.clone(),
+                        default.to_string(),
+                        attr.entity.identifier(),
+                    );
+                    self.report(Severity::Error, err, attr.span);
+                },
+                _ => (),
+            }
+        }
+
+        self.operations(&struc.name, &struc.attributes, &struc.operations);
+        self.inheritance(entity, struc);
+        self.interfaces(struc);
+        self.generics(struc);
+        self.types(struc.references());
+        self.keys(struc);
+        self.marked(struc);
+        self.event(struc);
+    }
+
+    /// Warns about operations marked as queries or commands of a structure without keys, which
+    /// targets cannot offer as root operations, as the object to call them on cannot be looked up.
+    fn marked(&mut self, struc: &Structure) {
+        if struc.is_interface() || struc.is_abstract() || !struc.keys().is_empty() {
+            return;
+        }
+        for op in struc.effective_operations().iter().filter(|op| op.kind.is_some()) {
+            let err = ParserError::UnkeyedOperation(struc.name.clone(), op.name.clone());
+            // Inherited operations are reported at the structure inheriting them.
+            let own = struc.operations.iter().any(|o| o.signature() == op.signature());
+            self.report(Severity::Warning, err, if own { op.span } else { struc.span });
+        }
+    }
+
+    fn service(&mut self, service: &Service) {
+        self.operations(&service.name, &[], &service.operations);
+        self.types(service.references());
+    }
+
+    /// Checks that the `operations` of `owner` are not named like its `attributes` or each
+    /// other and that their parameters are unique.
+    fn operations(&mut self, owner: &str, attributes: &[Attribute], operations: &[Operation]) {
+        for (index, op) in operations.iter().enumerate() {
+            if attributes.iter().any(|a| a.name == op.name) {
+                let err = ParserError::DuplicateFeature(owner.to_owned(), op.name.clone());
+                self.report(Severity::Error, err, op.span);
+            } else if operations[.. index].iter().any(|o| o.name == op.name) {
+                let err = ParserError::OverloadedOperation(owner.to_owned(), op.name.clone());
+                self.report(Severity::Warning, err, op.span);
+            }
+            for (index, param) in op.parameter.iter().enumerate() {
+                if op.parameter[.. index].iter().any(|p| p.name == param.name) {
+                    let err = ParserError::DuplicateParameter(op.name.clone(), param.name.clone());
+                    self.report(Severity::Error, err, param.span);
+                }
+            }
+            for event in op.emits.iter() {
+                if !matches!(event.entity().as_ref(), Entity::Structure(s) if s.is_event()) {
+                    let err = ParserError::NotAnEvent(op.name.clone(), event.identifier());
+                    self.report(Severity::Error, err, event.span());
+                }
+            }
+        }
+    }
+
+    /// Checks that events only carry data and are not extended, so every target can declare
+    /// them as plain records.
+    fn event(&mut self, struc: &Structure) {
+        if struc.is_event() {
+            let extended = struc.parent.is_some() || !struc.interfaces.is_empty();
+            if extended || struc.is_generic() || !struc.operations.is_empty() {
+                let err = ParserError::InvalidEvent(struc.name.clone());
+                self.report(Severity::Error, err, struc.span);
+            }
+        } else if let Some(parent) = &struc.parent {
+            if matches!(parent.entity().as_ref(), Entity::Structure(p) if p.is_event()) {
+                let err = ParserError::InheritsEvent(struc.name.clone(), parent.identifier());
+                self.report(Severity::Error, err, parent.span());
+            }
+        }
+    }
+
+    /// Checks that a relationship of `struc` references a structure and that both of its ends
+    /// agree on being the inverse of each other and on which end contains the other.
+    fn relationship(&mut self, struc: &Structure, attr: &Attribute) {
+        let composition = attr.ownership == Ownership::Composition;
+        let target = match attr.target() {
+            Some(target) => target,
+            None => {
+                if composition || attr.inverse.is_some() {
+                    let err = ParserError::NotARelationship(
+                        struc.name.clone(),
+                        attr.name.clone(),
+                        attr.entity.to_string(),
+                    );
+                    self.report(Severity::Error, err, attr.entity.span());
+                }
+                return;
+            },
+        };
+        let inverse = match attr.inverse_attribute(struc) {
+            Some(inverse) => inverse,
+            None => {
+                if let Some(inverse) = &attr.inverse {
+                    let err = ParserError::MissingInverse(
+                        struc.name.clone(),
+                        attr.name.clone(),
+                        target.identifier(),
+                        inverse.name.clone(),
+                    );
+                    self.report(Severity::Error, err, inverse.span);
+                }
+                return;
+            },
+        };
+        let span = attr.inverse.as_ref().map_or(attr.span, |i| i.span);
+        let mut owners: Vec<String> = struc.ancestors().iter().map(|a| a.identifier()).collect();
+        owners.push(struc.name.clone());
+        let referenced = inverse.entity.identifier();
+        if !matches!(inverse.target(), Some(e) if owners.contains(&e.identifier())) {
+            let err = ParserError::MismatchedInverse(
+                struc.name.clone(),
+                attr.name.clone(),
+                target.identifier(),
+                inverse.name.clone(),
+                referenced,
+            );
+            self.report(Severity::Error, err, span);
+            return;
+        }
+        match &inverse.inverse {
+            Some(named) if named.name != attr.name => {
+                let err = ParserError::ConflictingInverse(
+                    struc.name.clone(),
+                    attr.name.clone(),
+                    target.identifier(),
+                    inverse.name.clone(),
+                    named.name.clone(),
+                );
+                self.report(Severity::Error, err, span);
+            },
+            _ if composition && inverse.ownership == Ownership::Composition => {
+                let err = ParserError::MutualComposition(
+                    struc.name.clone(),
+                    attr.name.clone(),
+                    target.identifier(),
+                    inverse.name.clone(),
+                );
+                self.report(Severity::Error, err, attr.span);
+            },
+            _ if composition && inverse.multiplicity.bounds().1 != Some(1) => {
+                let err = ParserError::SharedPart(
+                    struc.name.clone(),
+                    attr.name.clone(),
+                    target.identifier(),
+                    inverse.name.clone(),
+                );
+                self.report(Severity::Error, err, attr.span);
+            },
+            _ => (),
+        }
+    }
+
+    /// Checks that the keys of `struc` are single values every target can look instances up
+    /// by, and that a generated key identifies the instances alone.
+    fn keys(&mut self, struc: &Structure) {
+        let declared: Vec<&Attribute> =
+            struc.attributes.iter().filter(|a| a.key.is_some()).collect();
+        for attr in declared.iter() {
+            if !matches!(attr.multiplicity, Multiplicity::Single) {
+                let err = ParserError::InvalidKey(struc.name.clone(), attr.name.clone());
+                self.report(Severity::Error, err, attr.span);
+            }
+            let valid = matches!(
+                attr.entity.entity().as_ref(),
+                Entity::Scalar(_) | Entity::CustomScalar(_) | Entity::Enum(_)
+            );
+            if !valid {
+                let err = ParserError::InvalidKeyType(
+                    struc.name.clone(),
+                    attr.name.clone(),
+                    attr.entity.to_string(),
+                );
+                self.report(Severity::Error, err, attr.entity.span());
+            }
+        }
+        let keys = struc.keys();
+        if let Some(first) = declared.first() {
+            if keys.len() > 1 && keys.iter().any(|k| k.key == Some(Key::Generated)) {
+                let err = ParserError::GeneratedCompositeKey(struc.name.clone());
+                self.report(Severity::Error, err, first.span);
+            }
+        }
+    }
+
+    /// Checks that the type parameters of `struc` are unique.
+    fn generics(&mut self, struc: &Structure) {
+        for (index, parameter) in struc.parameters.iter().enumerate() {
+            let name = parameter.identifier();
+            if struc.parameters[.. index].iter().any(|p| p.identifier() == name) {
+                let err = ParserError::DuplicateTypeParameter(struc.name.clone(), name);
+                self.report(Severity::Error, err, parameter.span().unwrap_or(struc.span));
+            }
+        }
+    }
+
+    /// Checks that `references` name types, pass as many type arguments as the referenced
+    /// entity takes and that maps have valid keys.
+    fn types(&mut self, references: Vec<&Reference>) {
+        for reference in references {
+            let entity = reference.entity();
+            if let Entity::Service(service) = entity.as_ref() {
+                let err = ParserError::ServiceAsType(service.name.clone());
+                self.report(Severity::Error, err, reference.span());
+                continue;
+            }
+            let expected = match entity.as_ref() {
+                Entity::Structure(generic) => generic.parameters.len(),
+                Entity::Map => 2,
+                _ => 0,
+            };
+            let given = reference.arguments().len();
+            if given != expected {
+                let err = ParserError::TypeArgumentCount(reference.identifier(), expected, given);
+                self.report(Severity::Error, err, reference.span());
+            } else if let (Entity::Map, Some(key)) =
+                (entity.as_ref(), reference.arguments().first())
+            {
+                // Every target has to be able to use the keys as the keys of its dictionaries.
+                let valid = matches!(
+                    key.entity().as_ref(),
+                    Entity::Scalar(_) | Entity::CustomScalar(_) | Entity::Enum(_)
+                );
+                if !valid {
+                    let err = ParserError::InvalidMapKey(key.to_string());
+                    self.report(Severity::Error, err, key.span());
+                }
+            }
+        }
+    }
+
+    /// Checks that the constraints of `attr` fit its type and each other, and that its default
+    /// value satisfies them.
+    fn constraints(&mut self, attr: &Attribute) {
+        let entity = attr.entity.entity();
+        for (index, constraint) in attr.constraints.iter().enumerate() {
+            if attr.constraints[.. index].iter().any(|c| c.kind == constraint.kind) {
+                let err = ParserError::DuplicateConstraint(
+                    attr.name.clone(),
+                    constraint.kind.to_string(),
+                );
+                self.report(Severity::Error, err, constraint.span);
+            } else if !constrains(entity.as_ref(), constraint) {
+                let err = ParserError::InvalidConstraint(
+                    attr.name.clone(),
+                    constraint.to_string(),
+                    entity.identifier(),
+                );
+                self.report(Severity::Error, err, constraint.span);
+            }
+        }
+
+        let find = |kind: ConstraintKind| attr.constraints.iter().find(|c| c.kind == kind);
+        let bounds = [
+            (ConstraintKind::Min, ConstraintKind::Max),
+            (ConstraintKind::MinLength, ConstraintKind::MaxLength),
+        ];
+        for (lower, upper) in bounds.iter() {
+            if let (Some(lower), Some(upper)) = (find(*lower), find(*upper)) {
+                if matches!((number(&lower.value), number(&upper.value)), (Some(l), Some(u)) if l > u)
+                {
+                    let err = ParserError::ContradictingConstraints(
+                        attr.name.clone(),
+                        lower.to_string(),
+                        upper.to_string(),
+                    );
+                    self.report(Severity::Error, err, upper.span);
+                }
+            }
+        }
+
+        if let Some(default) = &attr.default {
+            for constraint in &attr.constraints {
+                if violates(default, constraint) {
+                    let err = ParserError::DefaultViolatesConstraint(
+                        attr.name.clone(),
+                        constraint.to_string(),
+                    );
+                    self.report(Severity::Error, err, attr.span);
+                }
+            }
+        }
+    }
+
+    /// Checks that the parents of `struc` are structures which do not inherit from `struc` and
+    /// that inherited attributes are only redeclared with the same type and a narrower
+    /// multiplicity.
+    fn inheritance(&mut self, entity: &Rc<Entity>, struc: &Structure) {
+        let mut visited = vec![Rc::clone(entity)];
+        let mut redeclared: Vec<&Attribute> = struc.attributes.iter().collect();
+        let mut current = struc.parent.clone();
+        while let Some(reference) = current {
+            let parent = reference.entity();
+            if Rc::ptr_eq(&parent, entity) {
+                let err = ParserError::CyclicInheritance(struc.name.clone());
+                self.report(Severity::Error, err, struc.span);
+                return;
+            }
+            if visited.iter().any(|v| Rc::ptr_eq(v, &parent)) {
+                // The structures of the cycle report it themselves.
+                return;
+            }
+            visited.push(Rc::clone(&parent));
+
+            let parent_struc = match parent.as_ref() {
+                Entity::Structure(parent_struc) => parent_struc,
+                _ => {
+                    if visited.len() == 2 {
+                        let err =
+                            ParserError::InvalidParent(struc.name.clone(), parent.identifier());
+                        self.report(Severity::Error, err, reference.span());
+                    }
+                    return;
+                },
+            };
+            // Attributes are only compared with the closest ancestor declaring them.
+            let mut conflicts = vec![];
+            let mut widened = vec![];
+            redeclared.retain(|attr| {
+                match parent_struc.attributes.iter().find(|a| a.name == attr.name) {
+                    Some(inherited) => {
+                        if !Rc::ptr_eq(&inherited.entity.entity(), &attr.entity.entity()) {
+                            conflicts.push((*attr, inherited));
+                        } else if !attr.multiplicity.narrows(&inherited.multiplicity) {
+                            widened.push((*attr, inherited));
+                        }
+                        false
+                    },
+                    None => true,
+                }
+            });
+            for (attr, inherited) in conflicts {
+                let err = ParserError::ConflictingAttribute(
+                    struc.name.clone(),
+                    attr.name.clone(),
+                    attr.entity.identifier(),
+                    parent_struc.name.clone(),
+                    inherited.entity.identifier(),
+                );
+                self.report(Severity::Error, err, attr.span);
+            }
+            for (attr, inherited) in widened {
+                let err = ParserError::WideningOverride(
+                    struc.name.clone(),
+                    attr.name.clone(),
+                    quoted(&attr.multiplicity),
+                    parent_struc.name.clone(),
+                    quoted(&inherited.multiplicity),
+                );
+                self.report(Severity::Error, err, attr.span);
+            }
+            current = parent_struc.parent.clone();
+        }
+    }
+
+    /// Checks that interfaces are only implemented, never inherited from, and that concrete
+    /// structures provide every feature of the interfaces they and their ancestors implement.
+    fn interfaces(&mut self, struc: &Structure) {
+        if let Some(parent) = &struc.parent {
+            let parent_entity = parent.entity();
+            if struc.is_interface() {
+                let err = ParserError::InterfaceWithParent(struc.name.clone(), parent.identifier());
+                self.report(Severity::Error, err, parent.span());
+            } else if matches!(parent_entity.as_ref(), Entity::Structure(p) if p.is_interface()) {
+                let err = ParserError::InheritsInterface(struc.name.clone(), parent.identifier());
+                self.report(Severity::Error, err, parent.span());
+            }
+        }
+        for reference in &struc.interfaces {
+            let interface = reference.entity();
+            if !matches!(interface.as_ref(), Entity::Structure(s) if s.is_interface()) {
+                let err = ParserError::NotAnInterface(struc.name.clone(), reference.identifier());
+                self.report(Severity::Error, err, reference.span());
+            }
+        }
+        if struc.kind != StructureKind::Concrete {
+            return;
+        }
+
+        let ancestors = struc.ancestors();
+        let mut structures = vec![struc];
+        structures.extend(ancestors.iter().filter_map(as_structure));
+        for interface in struc.implemented() {
+            let interface = match as_structure(&interface) {
+                Some(interface) => interface,
+                None => continue,
+            };
+            for attr in &interface.attributes {
+                let provided = structures
+                    .iter()
+                    .find_map(|s| s.attributes.iter().find(|a| a.name == attr.name));
+                match provided {
+                    Some(provided) => {
+                        if !Rc::ptr_eq(&provided.entity.entity(), &attr.entity.entity()) {
+                            let err = ParserError::ConflictingAttribute(
+                                struc.name.clone(),
+                                attr.name.clone(),
+                                provided.entity.identifier(),
+                                interface.name.clone(),
+                                attr.entity.identifier(),
+                            );
+                            self.report(Severity::Error, err, struc.span);
+                        }
+                    },
+                    None => {
+                        let err = ParserError::MissingFeature(
+                            struc.name.clone(),
+                            attr.name.clone(),
+                            interface.name.clone(),
+                        );
+                        self.report(Severity::Error, err, struc.span);
+                    },
+                }
+            }
+            for op in &interface.operations {
+                if !structures.iter().any(|s| s.operations.iter().any(|o| o.name == op.name)) {
+                    let err = ParserError::MissingFeature(
+                        struc.name.clone(),
+                        op.name.clone(),
+                        interface.name.clone(),
+                    );
+                    self.report(Severity::Error, err, struc.span);
+                }
+            }
+        }
+    }
+
+    fn scalar(&mut self, custom: &CustomScalar) {
+        let base = custom.base.entity();
+        if !matches!(base.as_ref(), Entity::Scalar(_)) {
+            let err = ParserError::InvalidScalarBase(custom.name.clone(), base.identifier());
+            self.report(Severity::Error, err, custom.base.span());
+        }
+    }
+
+    /// Checks that the members of `union` are distinct concrete structures, which every target
+    /// can tell apart by their type.
+    fn union(&mut self, union: &Union) {
+        for (index, member) in union.members.iter().enumerate() {
+            let entity = member.entity();
+            let concrete = matches!(
+                entity.as_ref(),
+                Entity::Structure(s) if s.kind == StructureKind::Concrete && !s.is_generic()
+            );
+            if !concrete {
+                let err = ParserError::InvalidUnionMember(union.name.clone(), member.identifier());
+                self.report(Severity::Error, err, member.span());
+            } else if union.members[.. index].iter().any(|m| Rc::ptr_eq(&m.entity(), &entity)) {
+                let err =
+                    ParserError::DuplicateUnionMember(union.name.clone(), member.identifier());
+                self.report(Severity::Error, err, member.span());
+            }
+        }
+    }
+
+    /// Checks that the members of `enumeration` and their values are unique and that the values
+    /// share one type.
+    fn enumeration(&mut self, enumeration: &Enum) {
+        if enumeration.members.is_empty() {
+            let err = ParserError::EmptyEnum(enumeration.identifier.clone());
+            self.report(Severity::Warning, err, enumeration.span);
+        }
+        let strings =
+            enumeration.members.iter().any(|m| matches!(m.value, Some(EnumValue::String(_))));
+        // The values of the members before, including the ones they stand for without a value.
+        let mut values: Vec<Option<EnumValue>> = vec![];
+        for (index, member) in enumeration.members.iter().enumerate() {
+            let before = &enumeration.members[.. index];
+            if before.iter().any(|m| m.name == member.name) {
+                let err = ParserError::DuplicateEnumMember(
+                    enumeration.identifier.clone(),
+                    member.name.clone(),
+                );
+                self.report(Severity::Error, err, member.span);
+            }
+            let value = member.value.clone().or_else(|| implicit(member, strings, values.last()));
+            let first = before.iter().find_map(|m| m.value.as_ref());
+            match (&member.value, first) {
+                (Some(explicit), Some(first)) if kind(first) != kind(explicit) => {
+                    let err = ParserError::MixedEnumValues(
+                        enumeration.identifier.clone(),
+                        member.name.clone(),
+                        kind(explicit).to_owned(),
+                        kind(first).to_owned(),
+                    );
+                    self.report(Severity::Error, err, member.span);
+                },
+                _ if value.is_some() && values.contains(&value) => {
+                    let err = ParserError::DuplicateEnumValue(
+                        enumeration.identifier.clone(),
+                        value.as_ref().map(EnumValue::to_string).unwrap_or_default(),
+                    );
+                    self.report(Severity::Error, err, member.span);
+                },
+                _ => (),
+            }
+            values.push(value);
+        }
+    }
+}
+
+/// The value `member` without a value stands for, the way TypeScript assigns them: its name in
+/// enums of `strings`, otherwise the value after the `previous` one, starting at 0.
+fn implicit(
+    member: &EnumMember, strings: bool, previous: Option<&Option<EnumValue>>,
+) -> Option<EnumValue> {
+    match previous {
+        _ if strings => Some(EnumValue::String(member.name.clone())),
+        None => Some(EnumValue::Integer(0)),
+        Some(Some(EnumValue::Integer(previous))) => previous.checked_add(1).map(EnumValue::Integer),
+        Some(_) => None,
+    }
+}
+
+/// `multiplicity` for a message, quoted unless it is a single value, which is written without one.
+fn quoted(multiplicity: &Multiplicity) -> String {
+    match multiplicity {
+        Multiplicity::Single => multiplicity.to_string(),
+        multiplicity => format!("`{}`", multiplicity),
+    }
+}
+
+/// The kind of an enum value, which every member of an enum has to share.
+fn kind(value: &EnumValue) -> &'static str {
+    match value {
+        EnumValue::Integer(_) => "integer",
+        EnumValue::String(_) => "string",
+    }
+}
+
+fn as_structure(entity: &Rc<Entity>) -> Option<&Structure> {
+    match entity.as_ref() {
+        Entity::Structure(struc) => Some(struc),
+        _ => None,
+    }
+}
+
+/// Whether `constraint` can restrict the values of `entity`.
+fn constrains(entity: &Entity, constraint: &Constraint) -> bool {
+    let scalar = match entity.scalar() {
+        Some(scalar) => scalar,
+        None => return false,
+    };
+    match (constraint.kind, scalar, &constraint.value) {
+        (ConstraintKind::Min, Scalar::Integer, Value::Integer(_))
+        | (ConstraintKind::Max, Scalar::Integer, Value::Integer(_)) => true,
+        (
+            ConstraintKind::Min,
+            Scalar::Double | Scalar::Decimal,
+            Value::Integer(_) | Value::Double(_),
+        )
+        | (
+            ConstraintKind::Max,
+            Scalar::Double | Scalar::Decimal,
+            Value::Integer(_) | Value::Double(_),
+        ) => true,
+        (ConstraintKind::MinLength, Scalar::String, Value::Integer(length))
+        | (ConstraintKind::MaxLength, Scalar::String, Value::Integer(length)) => *length >= 0,
+        (ConstraintKind::Pattern, Scalar::String, Value::String(_)) => true,
+        _ => false,
+    }
+}
+
+/// Whether the default `value` violates `constraint`. Patterns are left to the targets.
+fn violates(value: &Value, constraint: &Constraint) -> bool {
+    let bound = match number(&constraint.value) {
+        Some(bound) => bound,
+        None => return false,
+    };
+    match (constraint.kind, value) {
+        (ConstraintKind::Min, value) => matches!(number(value), Some(n) if n < bound),
+        (ConstraintKind::Max, value) => matches!(number(value), Some(n) if n > bound),
+        (ConstraintKind::MinLength, Value::String(s)) => (s.chars().count() as f64) < bound,
+        (ConstraintKind::MaxLength, Value::String(s)) => (s.chars().count() as f64) > bound,
+        _ => false,
+    }
+}
+
+/// The number `value` stands for. Decimals may be written as strings, e.g. `"1.50"`.
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(integer) => Some(*integer as f64),
+        Value::Double(double) => Some(*double),
+        Value::String(string) => string.parse().ok().filter(|n: &f64| n.is_finite()),
+        _ => None,
+    }
+}
+
+/// Whether `value` can be the default of `attr`. Lists can only start empty, as there is no
+/// literal for their items.
+fn accepts(attr: &Attribute, value: &Value) -> bool {
+    match (attr.multiplicity, value) {
+        (UnderUpper(Discrete(0), _), Value::EmptyList) => return true,
+        (UnderUpper(_, _), _) | (Concrete(_), _) | (_, Value::EmptyList) => return false,
+        _ => (),
+    }
+    let entity = attr.entity.entity();
+    if let Entity::Enum(enumeration) = entity.as_ref() {
+        return matches!(value, Value::Member(name) if enumeration.members.iter().any(|m| &m.name == name));
+    }
+    // Dates, identifiers and decimals are written as strings, the ones of decimals have to be
+    // numbers.
+    match (entity.scalar(), value) {
+        (Some(Scalar::String), Value::String(_)) => true,
+        (Some(Scalar::Character), Value::String(s)) => s.chars().count() == 1,
+        (Some(Scalar::Date | Scalar::DateTime | Scalar::Uuid), Value::String(_)) => true,
+        (Some(Scalar::Decimal), Value::String(_)) => number(value).is_some(),
+        (Some(Scalar::Decimal), Value::Integer(_) | Value::Double(_)) => true,
+        (Some(Scalar::Integer), Value::Integer(_)) => true,
+        (Some(Scalar::Double), Value::Integer(_) | Value::Double(_)) => true,
+        (Some(Scalar::Boolean), Value::Boolean(_)) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::check;
+    use crate::diagnostic::Severity;
+    use crate::error::ParserError;
+    use crate::validation::check_annotations;
+
+    fn diagnostics(code: &str) -> Vec<(Severity, ParserError)> {
+        let (_, diagnostics) = check(code);
+        diagnostics.into_iter().map(|d| (d.severity, d.error)).collect()
+    }
+
+    #[test]
+    fn test_valid_model() {
+        let code = "org.mobile { Vehicle { name: String, tires: Tire [+] } Tire { size: Integer } \
+                    Bike : Vehicle { tires: Tire [2,4] } }";
+        assert!(diagnostics(code).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_attribute() {
+        let found = diagnostics("org.a { A { b: String, b: Integer } }");
+        assert_eq!(found.len(), 1);
+        assert!(matches!(found[0], (Severity::Error, ParserError::DuplicateFeature(_, _))));
+    }
+
+    #[test]
+    fn test_conflicting_attribute() {
+        let found =
+            diagnostics("org.a { A { b: String } B : A { b: Integer } C : B { b: Double } }");
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|d| matches!(d.1, ParserError::ConflictingAttribute(..))));
+    }
+
+    #[test]
+    fn test_widening_override() {
+        let code = "org.a { A { b: String [+], c: String?, d: String [2, 5] } B : A { b: String \
+                    [*], c: String, d: String } C : A { d: String [3] } }";
+        let found: Vec<String> =
+            diagnostics(code).into_iter().map(|(_, e)| e.to_string()).collect();
+        assert_eq!(found, vec![
+            "`B.b` with multiplicity `[*]` does not narrow `A.b` with multiplicity `[+]`.",
+            "`B.d` with multiplicity exactly one does not narrow `A.d` with multiplicity `[2, 5]`.",
+        ]);
+    }
+
+    #[test]
+    fn test_invalid_multiplicity() {
+        let found = diagnostics("org.a { A { b: String [5,2] } }");
+        assert!(matches!(found[0], (Severity::Error, ParserError::InvalidMultiplicity(5, 2))));
+    }
+
+    #[test]
+    fn test_duplicate_enum_members() {
+        let found = diagnostics("org.a { E { a = 1, b = 1, a } }");
+        assert_eq!(found.len(), 2);
+        assert!(matches!(found[0].1, ParserError::DuplicateEnumValue(_, _)));
+        assert!(matches!(found[1].1, ParserError::DuplicateEnumMember(_, _)));
+    }
+
+    #[test]
+    fn test_implicit_enum_values() {
+        let code = "org.a { A { a, b, c = 5, d } B { a = 1, b = 0, c = 3 } C { a = \"x\", b } }";
+        assert!(diagnostics(code).is_empty());
+        let found =
+            diagnostics("org.a { E { a = 1, b, c = 2 } F { a, b = 0 } G { a = \"b\", b } }");
+        let found: Vec<String> = found.into_iter().map(|(_, e)| e.to_string()).collect();
+        assert_eq!(found, vec![
+            "Value `2` is used by more than one member of `E`.",
+            "Value `0` is used by more than one member of `F`.",
+            "Value `\"b\"` is used by more than one member of `G`.",
+        ]);
+    }
+
+    #[test]
+    fn test_duplicate_parameter() {
+        let found = diagnostics("org.a { A { op(a: String, a: Integer) } }");
+        assert!(matches!(found[0], (Severity::Error, ParserError::DuplicateParameter(_, _))));
+    }
+
+    #[test]
+    fn test_warnings() {
+        let (package, diagnostics) = check("org.a { E { } A { op(), op(a: String) } }");
+        assert!(package.is_some());
+        assert!(!diagnostics.has_errors());
+        let severities: Vec<Severity> = diagnostics.iter().map(|d| d.severity).collect();
+        assert_eq!(severities, vec![Severity::Warning, Severity::Warning]);
+    }
+
+    #[test]
+    fn test_unkeyed_operations() {
+        let code = "org.a { A { name: String, query countAll(): Integer, command reset(), other() \
+                    } B { key id: String, query count(): Integer } abstract C { query d() } }";
+        let (package, diagnostics) = check(code);
+        assert!(package.is_some());
+        let found: Vec<String> = diagnostics.iter().map(|d| d.error.to_string()).collect();
+        assert_eq!(found, vec![
+            "Operation `countAll` is marked as a query or command, but `A` has no key to look up \
+             the object it is called on.",
+            "Operation `reset` is marked as a query or command, but `A` has no key to look up the \
+             object it is called on.",
+        ]);
+    }
+
+    #[test]
+    fn test_cyclic_inheritance() {
+        let found = diagnostics("org.a { A : B { } B : A { } C : String { } D : A { } }");
+        assert_eq!(found.len(), 3);
+        assert!(matches!(found[0].1, ParserError::CyclicInheritance(_)));
+        assert!(matches!(found[1].1, ParserError::CyclicInheritance(_)));
+        assert!(matches!(found[2].1, ParserError::InvalidParent(_, _)));
+    }
+
+    #[test]
+    fn test_unknown_annotations() {
+        let code = "org.a { @graphql(skip) A { @id a: String, @unique @other b: String } }";
+        let (package, mut diagnostics) = check(code);
+        let targets = vec![String::from("graphql"), String::from("typescript")];
+        check_annotations(&package.unwrap(), "typescript", &["id"], &targets, &mut diagnostics);
+        let found: Vec<String> = diagnostics.iter().map(|d| d.error.to_string()).collect();
+        assert_eq!(found, vec![
+            "`@unique` is not understood by target `typescript` and is ignored.",
+            "`@other` is not understood by target `typescript` and is ignored.",
+        ]);
+        assert!(!diagnostics.has_errors());
+    }
+
+    #[test]
+    fn test_defaults() {
+        let code = "org.a { S { red, green } A { a: Boolean = true, b: Double = 1, c: S = red, d: \
+                    String [*] = [], e: Character? = \"x\" } }";
+        assert!(diagnostics(code).is_empty());
+        let code = "org.a { S { red } A { a: Integer = \"1\", b: S = blue, c: String [+] = [], d: \
+                    String = [], e: A = red } }";
+        let found = diagnostics(code);
+        assert_eq!(found.len(), 5);
+        assert!(found
+            .iter()
+            .all(|f| matches!(f, (Severity::Error, ParserError::InvalidDefault(..)))));
+        assert_eq!(
+            found[0].1.to_string(),
+            "`\"1\"` is not a valid default value for `a` of type `Integer`."
+        );
+    }
+
+    #[test]
+    fn test_constraints() {
+        let code = "org.a { A { a: Integer (min 0, max 150) = 18, b: String [*] (pattern \
+                    \"[a-z]+\", maxLength 3), c: Double (min 0.5) } }";
+        assert!(diagnostics(code).is_empty());
+        let code = "org.a { A { a: String (min 0), b: Integer (min 1.5), c: String (maxLength \
+                    -1), d: Integer (min 5, max 1), e: String (maxLength 2, maxLength 3), f: \
+                    Integer (max 1) = 2 } }";
+        let found: Vec<ParserError> = diagnostics(code).into_iter().map(|(_, e)| e).collect();
+        assert_eq!(found.len(), 6);
+        assert!(matches!(found[0], ParserError::InvalidConstraint(..)));
+        assert!(matches!(found[1], ParserError::InvalidConstraint(..)));
+        assert!(matches!(found[2], ParserError::InvalidConstraint(..)));
+        assert_eq!(found[3].to_string(), "`min 5` contradicts `max 1` of `d`.");
+        assert!(matches!(found[4], ParserError::DuplicateConstraint(..)));
+        assert_eq!(found[5].to_string(), "The default value of `f` violates `max 1`.");
+    }
+
+    #[test]
+    fn test_decimal_constraints() {
+        let code = "org.a { scalar Money : Decimal A { a: Decimal (min 0, max 99.99) = \"1.50\", \
+                    b: Money (min 0.01) } }";
+        assert!(diagnostics(code).is_empty());
+        let code = "org.a { A { a: Decimal (min \"0\"), b: Decimal (maxLength 3), c: Decimal (min \
+                    0) = \"-1\", d: Decimal (max 1) = 1.5, e: Decimal (min 0) = \"abc\", f: \
+                    Decimal = \"inf\" } }";
+        let found: Vec<ParserError> = diagnostics(code).into_iter().map(|(_, e)| e).collect();
+        assert_eq!(found.len(), 6);
+        assert!(matches!(found[0], ParserError::InvalidConstraint(..)));
+        assert!(matches!(found[1], ParserError::InvalidConstraint(..)));
+        assert_eq!(found[2].to_string(), "The default value of `c` violates `min 0`.");
+        assert_eq!(found[3].to_string(), "The default value of `d` violates `max 1`.");
+        assert!(matches!(found[4], ParserError::InvalidDefault(..)));
+        assert!(matches!(found[5], ParserError::InvalidDefault(..)));
+    }
+
+    #[test]
+    fn test_scalars() {
+        let code = "org.a { scalar Email : String scalar Money : Decimal A { a: Email (maxLength \
+                    3) = \"a@b\", b: Money = 1.5, c: Date = \"2020-01-01\" } }";
+        assert!(diagnostics(code).is_empty());
+        let code = "org.a { scalar B : A scalar C : B A { a: String } }";
+        let found = diagnostics(code);
+        assert_eq!(found.len(), 2);
+        assert_eq!(
+            found[0].1.to_string(),
+            "Scalar `B` can only be based on a built-in scalar, but `A` is not one."
+        );
+    }
+
+    #[test]
+    fn test_interfaces() {
+        let code = "org.a { interface Named { name: String, greet() } interface Aged implements \
+                    Named { age: Integer } abstract Base implements Named { name: String } Person \
+                    : Base implements Aged { age: Integer, greet() } }";
+        assert!(diagnostics(code).is_empty());
+        let code = "org.a { interface Named { name: String, greet() } Person implements Named { \
+                    name: Integer } Pet : Named { a: String } interface Thing : Pet { a: String } \
+                    Car implements Pet { a: String } }";
+        let found: Vec<String> =
+            diagnostics(code).into_iter().map(|(_, e)| e.to_string()).collect();
+        assert_eq!(found, vec![
+            "`Person.name` of type `Integer` conflicts with `Named.name` of type `String`.",
+            "`Person` does not provide `greet` of interface `Named`.",
+            "`Pet` cannot inherit from interface `Named`, it has to implement it instead.",
+            "Interface `Thing` cannot inherit from `Pet`, interfaces can only implement other \
+             interfaces.",
+            "`Car` can only implement interfaces, but `Pet` is not one.",
+        ]);
+    }
+
+    #[test]
+    fn test_generics() {
+        let code = "org.a { Page<T> { items: T [*], total: Integer, first(): T } Pair<A, B> { a: \
+                    A, b: B } Order { pages: Page<Pair<Order, Integer>> } }";
+        assert!(diagnostics(code).is_empty());
+        let code = "org.a { Page<T, T> { items: T [*] } Order { a: Page, b: Page<Order, Integer>, \
+                    c: Order<Page<Order>> } }";
+        let found: Vec<String> =
+            diagnostics(code).into_iter().map(|(_, e)| e.to_string()).collect();
+        assert_eq!(found, vec![
+            "Type parameter `T` is declared more than once by `Page`.",
+            "`Page` takes 2 type arguments, but 0 are given.",
+            "`Order` takes 0 type arguments, but 1 are given.",
+            "`Page` takes 2 type arguments, but 1 are given.",
+        ]);
+    }
+
+    #[test]
+    fn test_maps() {
+        let code = "org.a { Color { red, green } Order { a: Map<String, Integer>, b: Map<Color, \
+                    Map<Date, Order>> [*] } }";
+        assert!(diagnostics(code).is_empty());
+        let code = "org.a { Order { a: Map<Order, String>, b: Map<String>, c: Map } }";
+        let found: Vec<String> =
+            diagnostics(code).into_iter().map(|(_, e)| e.to_string()).collect();
+        assert_eq!(found, vec![
+            "Maps can only be keyed by scalars and enums, but `Order` is neither.",
+            "`Map` takes 2 type arguments, but 1 are given.",
+            "`Map` takes 2 type arguments, but 0 are given.",
+        ]);
+    }
+
+    #[test]
+    fn test_keys() {
+        let code = "org.a { Color { red } A { key a: String, key b: Color, key: Integer } B { \
+                    generated key id: UUID } C : B { c: String } }";
+        assert!(diagnostics(code).is_empty());
+        let code = "org.a { A { key a: String [*], key b: B? } B { generated key id: Integer, key \
+                    c: String } C : B { generated key d: UUID } }";
+        let found: Vec<String> =
+            diagnostics(code).into_iter().map(|(_, e)| e.to_string()).collect();
+        assert_eq!(found, vec![
+            "Key `A.a` has to be exactly one value.",
+            "Key `A.b` has to be exactly one value.",
+            "Key `A.b` has to be a scalar or an enum, but `B` is neither.",
+            "A generated key has to identify the instances of `B` alone.",
+            "A generated key has to identify the instances of `C` alone.",
+        ]);
+    }
+
+    #[test]
+    fn test_relationships() {
+        let code = "org.a { Customer { orders: Order [*] inverse customer } Order { customer: \
+                    Customer, contains lines: Line [+] inverse order } Line { order: Order } }";
+        assert!(diagnostics(code).is_empty());
+        let code = "org.a { A { contains b: String, c: B inverse d, e: B inverse f, g: B inverse \
+                    h, contains i: B inverse j } B { f: String, h: B inverse x, contains j: A [*] \
+                    inverse i } }";
+        let found: Vec<String> =
+            diagnostics(code).into_iter().map(|(_, e)| e.to_string()).collect();
+        assert_eq!(found, vec![
+            "`A.b` cannot be a relationship, as `String` is not a structure.",
+            "`B` has no attribute `d`, which `A.c` names as its inverse.",
+            "`B.f` cannot be the inverse of `A.e`, as it references `String` instead of `A`.",
+            "`B.h` cannot be the inverse of `A.g`, as it references `B` instead of `A`.",
+            "`A.i` and `B.j` cannot both contain the other end of their relationship.",
+            "`B` has no attribute `x`, which `B.h` names as its inverse.",
+            "`B.j` and `A.i` cannot both contain the other end of their relationship.",
+        ]);
+        let code = "org.a { A { contains b: B [*] } B { a: A [*] inverse b } }";
+        let found: Vec<String> =
+            diagnostics(code).into_iter().map(|(_, e)| e.to_string()).collect();
+        assert_eq!(found, vec![
+            "`B.a` has to be a single value, as the parts `A.b` contains belong to one `A`.",
+        ]);
+    }
+
+    #[test]
+    fn test_unions() {
+        let code = "org.a { union Found = A | B A { a: Found [*] } B { b: String } }";
+        assert!(diagnostics(code).is_empty());
+        let code = "org.a { union Found = A | String | A | I | P interface I { } P<T> { t: T } A \
+                    { a: String } }";
+        let found: Vec<String> =
+            diagnostics(code).into_iter().map(|(_, e)| e.to_string()).collect();
+        assert_eq!(found, vec![
+            "`String` cannot be a member of union `Found`, only concrete structures can.",
+            "`A` is a member of union `Found` more than once.",
+            "`I` cannot be a member of union `Found`, only concrete structures can.",
+            "`P` cannot be a member of union `Found`, only concrete structures can.",
+        ]);
+    }
+
+    #[test]
+    fn test_services() {
+        let code = "org.a { service S { query a(): A, command b(a: A) } A { a: String } }";
+        assert!(diagnostics(code).is_empty());
+        let code = "org.a { service S { a(s: S), a(), b(x: A, x: A): S } A { s: S } }";
+        let found: Vec<(Severity, String)> =
+            diagnostics(code).into_iter().map(|(s, e)| (s, e.to_string())).collect();
+        assert_eq!(found, vec![
+            (
+                Severity::Warning,
+                String::from(
+                    "Operation `a` of `S` is overloaded, which is not supported by every target."
+                )
+            ),
+            (
+                Severity::Error,
+                String::from("Parameter `x` is declared more than once in operation `b`.")
+            ),
+            (Severity::Error, String::from("`S` is a service and cannot be used as a type.")),
+            (Severity::Error, String::from("`S` is a service and cannot be used as a type.")),
+            (Severity::Error, String::from("`S` is a service and cannot be used as a type.")),
+        ]);
+    }
+
+    #[test]
+    fn test_events() {
+        let code = "org.a { event E { a: A } service S { a(): A emits E } A { a: String } }";
+        assert!(diagnostics(code).is_empty());
+        let code = "org.a { event E { b(): A } event F : E { a: A } B : E { a: A } A { a(): A \
+                    emits A, E } }";
+        let found: Vec<String> =
+            diagnostics(code).into_iter().map(|(_, e)| e.to_string()).collect();
+        assert_eq!(found, vec![
+            "Event `E` can only declare attributes, without type parameters, parents, interfaces \
+             or operations.",
+            "Event `F` can only declare attributes, without type parameters, parents, interfaces \
+             or operations.",
+            "`B` cannot inherit from event `E`, events cannot be extended.",
+            "`A` is emitted by operation `a`, but it is not an event.",
+        ]);
+    }
+}
@@ -1,8 +1,12 @@
 use ast::Multiplicity::{Concrete, Optional, UnderUpper};
 use ast::Number::Discrete;
-use ast::{Entity, Identifying, Namespace};
+use ast::{
+    Annotated, Annotation, Attribute, Constraint, Entity, Identifying, Key, Operation,
+    OperationKind, Ownership, Package, Reference, Scalar, Structure, Value,
+};
 use generator::Transform;
 use parser::parse_from_file;
+use std::rc::Rc;
 use util::buffer::Buffer;
 use util::code::{CodePage, GeneratedCode};
 use util::error::PakResult;
@@ -15,9 +19,11 @@ pub struct GraphQLTarget {}
 impl Target for GraphQLTarget {
     fn name(&self) -> &'static str { "graphql" }
 
+    fn annotations(&self) -> &'static [&'static str] { &["id", "deprecated", "graphql"] }
+
     fn generate_from(&self, model: Model) -> PakResult<FileStructure> {
-        let namespace = parse_from_file(model.path.as_path())?;
-        let transformed = Document::transform(&namespace);
+        let package = parse_from_file(model.path.as_path())?;
+        let transformed = Document::transform(&package);
         let schema = transformed.generate();
         let file_structure = FileStructure::Dir("graphql".to_owned(), vec![FileStructure::File(
             "schema.graphqls".to_owned(),
@@ -43,33 +49,347 @@ struct Mutation {
     mutations: Vec<String>,
 }
 
+/// The root type clients subscribe to the events of the model with.
+#[derive(Debug, Clone)]
+struct Subscription {
+    subscriptions: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
 struct Schema {
     query: Query,
     mutation: Mutation,
+    /// The subscriptions, for models declaring events.
+    subscription: Option<Subscription>,
 }
 
 #[derive(Debug, Clone)]
 struct Type {
     name: String,
+    description: Option<String>,
     fields: Vec<Field>,
+    /// Whether the type is an `interface`, which interfaces and abstract structures become.
+    interface: bool,
+    /// Whether the type is an instance of a generic structure, e.g. `PageOfOrder`, which is only
+    /// reached through the fields using it.
+    instance: bool,
+    /// Whether the objects of the type are parts another type contains, which are only reached
+    /// through the object containing them.
+    part: bool,
+    /// Whether the type is an event, which clients subscribe to instead of querying it.
+    event: bool,
+    /// The fields for the operations of the structure, along with whether the operations are
+    /// marked as queries or commands, which are root fields as well.
+    operations: Vec<(Option<OperationKind>, Field)>,
+    implements: Vec<String>,
+    /// Where the entity of the type is declared in the model.
+    source: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 enum Typed {
     Type(Type),
+    Scalar(ScalarType),
+    Union(UnionType),
+    Enum(EnumType),
+    Input(InputType),
+    Service(ServiceType),
     None,
 }
 
+/// An `input` type mirroring an object type, as arguments cannot be object types, e.g.
+/// `PersonInput` for `Person`.
+#[derive(Debug, Clone)]
+struct InputType {
+    name: String,
+    description: Option<String>,
+    fields: Vec<Field>,
+    /// Where the entity of the mirrored type is declared in the model.
+    source: Option<String>,
+}
+
+/// A `scalar` declaration, for scalars of the model GraphQL does not provide.
+#[derive(Debug, Clone)]
+struct ScalarType {
+    name: String,
+    description: Option<String>,
+    /// Where the scalar is declared in the model, if it is not built in.
+    source: Option<String>,
+}
+
+/// A `union` of object types.
+#[derive(Debug, Clone)]
+struct UnionType {
+    name: String,
+    description: Option<String>,
+    members: Vec<String>,
+    /// Where the union is declared in the model.
+    source: Option<String>,
+}
+
+/// An `enum` of the members of an enum. GraphQL only knows the names of the members, the values
+/// they stand for stay with the server.
+#[derive(Debug, Clone)]
+struct EnumType {
+    name: String,
+    description: Option<String>,
+    members: Vec<EnumMemberType>,
+    /// Where the enum is declared in the model.
+    source: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct EnumMemberType {
+    name: String,
+    description: Option<String>,
+    directives: Vec<String>,
+}
+
+/// The operations of a service, which become fields of the root types.
+#[derive(Debug, Clone)]
+struct ServiceType {
+    /// The fields of `Query`, for the operations marked as `query`.
+    queries: Vec<Field>,
+    /// The fields of `Mutation`, for the commands.
+    mutations: Vec<Field>,
+}
+
+/// The GraphQL types of the built-in scalars.
+const SCALARS: [(Scalar, &str); 9] = [
+    (Scalar::String, "String"),
+    (Scalar::Integer, "Int"),
+    (Scalar::Double, "Float"),
+    (Scalar::Boolean, "Boolean"),
+    (Scalar::Character, "String"),
+    (Scalar::Date, "Date"),
+    (Scalar::DateTime, "DateTime"),
+    (Scalar::Uuid, "UUID"),
+    (Scalar::Decimal, "Decimal"),
+];
+
+/// The scalars every GraphQL schema provides.
+const NATIVE_SCALARS: [&str; 5] = ["Int", "Float", "String", "Boolean", "ID"];
+
+/// The name of the GraphQL type of `entity`.
+fn type_name(entity: &Entity) -> String {
+    match entity {
+        Entity::Scalar(scalar) => {
+            let found = SCALARS.iter().find(|(s, _)| s == scalar);
+            found.map(|(_, name)| *name).expect("Every scalar should be mapped").to_owned()
+        },
+        entity => entity.identifier(),
+    }
+}
+
+/// The GraphQL type `reference` refers to. GraphQL has no generics, so instances of generic
+/// structures are types of their own, e.g. `PageOfOrder` for `Page<Order>`, and maps are lists
+/// of entries, e.g. `[StringToIntEntry!]` for `Map<String, Integer>`.
+fn reference_name(reference: &Reference) -> String {
+    match reference.entity().as_ref() {
+        Entity::Map => format!("[{}Entry!]", label(reference)),
+        _ => label(reference),
+    }
+}
+
+/// The name of the type `reference` refers to, which for a map names its entries.
+fn label(reference: &Reference) -> String {
+    let name = type_name(reference.entity().as_ref());
+    let arguments: Vec<String> = reference.arguments().iter().map(label).collect();
+    match reference.entity().as_ref() {
+        Entity::Map => arguments.join("To"),
+        _ if arguments.is_empty() => name,
+        _ => format!("{}Of{}", name, arguments.join("And")),
+    }
+}
+
+/// The GraphQL type `reference` refers to as an argument. Arguments cannot be object types, so
+/// structures and the entries of maps are replaced by their input types, e.g. `OrderInput` for
+/// `Order`. Unions have no input types, so they cannot be arguments at all.
+fn input_name(reference: &Reference) -> Option<String> {
+    match reference.entity().as_ref() {
+        Entity::Map => Some(format!("[{}EntryInput!]", label(reference))),
+        Entity::Structure(_) => Some(format!("{}Input", label(reference))),
+        Entity::Union(_) => None,
+        _ => Some(label(reference)),
+    }
+}
+
+/// The type of the entries of the map `reference` refers to, with a `key` and a `value`.
+fn entry(reference: &Reference) -> Type {
+    let field = |name: &str, argument: &Reference| Field {
+        input: input_name(argument).map(|input| input + "!"),
+        ..Field::new(name, reference_name(argument) + "!")
+    };
+    let arguments = reference.arguments();
+    Type {
+        name: format!("{}Entry", label(reference)),
+        description: None,
+        fields: vec![field("key", &arguments[0]), field("value", &arguments[1])],
+        interface: false,
+        implements: vec![],
+        instance: true,
+        part: false,
+        event: false,
+        operations: vec![],
+        source: None,
+    }
+}
+
+/// The instances of generic structures and the maps the fields of the model use, e.g.
+/// `Page<Order>`, along with the ones those use themselves. Each instance is listed once.
+fn instances(model: &Package) -> Vec<Reference> {
+    let mut pending: Vec<Reference> = vec![];
+    for namespace in &model.namespaces {
+        for entity in &namespace.entities {
+            match entity.as_ref() {
+                Entity::Structure(struc)
+                    if !struc.is_generic() && !struc.has_hint("graphql", "skip") =>
+                {
+                    pending.extend(struc.effective_attributes().into_iter().map(|a| a.entity));
+                    let operations = struc.effective_operations();
+                    pending.extend(operations.iter().flat_map(|o| o.references()).cloned());
+                },
+                Entity::Service(service) if !service.has_hint("graphql", "skip") => {
+                    pending.extend(service.references().into_iter().cloned());
+                },
+                _ => (),
+            }
+        }
+    }
+    let mut instances: Vec<Reference> = vec![];
+    let mut index = 0;
+    while index < pending.len() {
+        let reference = pending[index].clone();
+        index += 1;
+        let entity = reference.entity();
+        let name = label(&reference);
+        if reference.arguments().is_empty() || instances.iter().any(|i| label(i) == name) {
+            continue;
+        }
+        match entity.as_ref() {
+            Entity::Structure(generic) => {
+                let instance = generic.instantiate(reference.arguments());
+                pending.extend(instance.effective_attributes().into_iter().map(|a| a.entity));
+                let operations = instance.effective_operations();
+                pending.extend(operations.iter().flat_map(|o| o.references()).cloned());
+            },
+            _ => pending.extend(reference.arguments().iter().cloned()),
+        }
+        instances.push(reference);
+    }
+    instances
+}
+
+/// The names of the types whose objects are contained by other objects, e.g. `OrderLine` of
+/// `contains lines: OrderLine [+]`. They are created, changed and deleted along with the object
+/// containing them.
+fn parts(model: &Package) -> Vec<String> {
+    let mut parts = vec![];
+    for entity in model.entities() {
+        match entity.as_ref() {
+            Entity::Structure(struc) if !struc.is_generic() => {
+                let attributes = struc.attributes.iter();
+                let compositions = attributes.filter(|a| a.ownership == Ownership::Composition);
+                parts.extend(compositions.map(|a| reference_name(&a.entity)));
+            },
+            _ => (),
+        }
+    }
+    parts
+}
+
 #[derive(Debug, Clone)]
 struct Field {
     name: String,
+    description: Option<String>,
     typ: String,
+    directives: Vec<String>,
+    /// The default value of the field as an input.
+    default: Option<String>,
+    /// The `@constraint` directive restricting the values of the field.
+    constraint: Option<String>,
+    /// Whether the field identifies the objects of its type.
+    key: Option<Key>,
+    /// The type of the field as an argument or a field of an input type, e.g. `[TireInput]!`,
+    /// unless it is a union, which cannot be one.
+    input: Option<String>,
+    /// The arguments of the field for an operation.
+    arguments: Vec<Field>,
 }
 
-impl Transform<Namespace> for Document {
-    fn transform(model: &Namespace) -> Self {
-        let types: Vec<Typed> = model.entities.iter().map(|e| Typed::transform(e)).collect();
+impl Type {
+    /// The fields identifying the objects of the type.
+    fn keys(&self) -> Vec<&Field> { self.fields.iter().filter(|f| f.key.is_some()).collect() }
+}
+
+impl Transform<Package> for Document {
+    fn transform(model: &Package) -> Self {
+        let mut types: Vec<Typed> = vec![];
+        let mut used: Vec<Scalar> = vec![];
+        let instances = instances(model);
+        for namespace in &model.namespaces {
+            for entity in &namespace.entities {
+                let mut typeds = vec![];
+                match entity.as_ref() {
+                    // Generic structures are only declared through their instances.
+                    Entity::Structure(generic) if generic.is_generic() => {
+                        for reference in
+                            instances.iter().filter(|i| Rc::ptr_eq(&i.entity(), entity))
+                        {
+                            let instance =
+                                Entity::Structure(generic.instantiate(reference.arguments()));
+                            let mut typed = Typed::transform(&instance);
+                            if let Typed::Type(typ) = &mut typed {
+                                typ.name = reference_name(reference);
+                                typ.instance = true;
+                            }
+                            typeds.push(typed);
+                            used.extend(scalars(&instance));
+                        }
+                    },
+                    _ => {
+                        typeds.push(Typed::transform(entity));
+                        used.extend(scalars(entity));
+                    },
+                }
+                let source = entity.span().map(|span| namespace.locate(span));
+                for mut typed in typeds {
+                    match &mut typed {
+                        Typed::Type(typ) => typ.source = source.clone(),
+                        Typed::Scalar(scalar) => scalar.source = source.clone(),
+                        Typed::Union(union) => union.source = source.clone(),
+                        Typed::Enum(enumeration) => enumeration.source = source.clone(),
+                        Typed::Input(_) | Typed::Service(_) | Typed::None => (),
+                    }
+                    types.push(typed);
+                }
+            }
+        }
+        for map in instances.iter().filter(|i| matches!(i.entity().as_ref(), Entity::Map)) {
+            for argument in map.arguments() {
+                if let Entity::Scalar(scalar) = argument.entity().as_ref() {
+                    used.push(*scalar);
+                }
+            }
+            types.push(Typed::Type(entry(map)));
+        }
+        // Built-in scalars of the model which GraphQL lacks are declared once they are used.
+        let declared = SCALARS
+            .iter()
+            .filter(|(scalar, name)| used.contains(scalar) && !NATIVE_SCALARS.contains(name))
+            .map(|(_, name)| {
+                let name = String::from(*name);
+                Typed::Scalar(ScalarType { name, description: None, source: None })
+            });
+        types.splice(0 .. 0, declared.collect::<Vec<Typed>>());
+        let parts = parts(model);
+        for typed in types.iter_mut() {
+            if let Typed::Type(typ) = typed {
+                typ.part = parts.contains(&typ.name);
+            }
+        }
+        let inputs = inputs(&types);
+        types.extend(inputs.into_iter().map(Typed::Input));
         let schema = Schema::transform(&types);
         Document { types, schema }
     }
@@ -79,7 +399,41 @@ impl Transform<Vec<Typed>> for Schema {
     fn transform(model: &Vec<Typed>) -> Self {
         let query = Query::transform(model);
         let mutation = Mutation::transform(model);
-        Schema { query, mutation }
+        let subscription = Subscription::transform(model);
+        let subscription = Some(subscription).filter(|s| !s.subscriptions.is_empty());
+        Schema { query, mutation, subscription }
+    }
+}
+
+impl Transform<Vec<Typed>> for Subscription {
+    fn transform(model: &Vec<Typed>) -> Self {
+        let mut subscriptions: Vec<String> = vec![];
+        for t in model {
+            if let Typed::Type(typ) = t {
+                if typ.event {
+                    subscriptions.push(format!("{}: {}!", lower_first(&typ.name), typ.name));
+                }
+            }
+        }
+        Subscription { subscriptions }
+    }
+}
+
+/// `name` starting with a lower case letter, e.g. `orderPlaced` for `OrderPlaced`.
+fn lower_first(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// `name` starting with an upper case letter, e.g. `CountTires` for `countTires`.
+fn upper_first(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
     }
 }
 
@@ -87,10 +441,27 @@ impl Transform<Vec<Typed>> for Query {
     fn transform(model: &Vec<Typed>) -> Self {
         let mut queries: Vec<String> = vec![];
         for t in model {
+            if let Typed::Service(service) = t {
+                queries.extend(service.queries.iter().map(Field::generate));
+            }
             if let Typed::Type(typ) = t {
-                // TODO one for id,
+                // Interfaces, instances of generic structures and parts can only be queried
+                // through the types using them, events are subscribed to.
+                if typ.interface || typ.instance || typ.part || typ.event {
+                    continue;
+                }
                 let query = format!("query{}: [{}!]", &typ.name, &typ.name);
                 queries.push(query);
+                let keys: Vec<String> =
+                    typ.keys().iter().filter_map(|k| k.argument(false)).collect();
+                if !keys.is_empty() {
+                    let name = &typ.name;
+                    queries.push(format!("find{}ById({}): {}", name, keys.join(", "), name));
+                    // Operations are called on an object, which their root fields look up.
+                    let operations = typ.operations.iter();
+                    let operations = operations.filter(|(k, _)| *k == Some(OperationKind::Query));
+                    queries.extend(operations.map(|(_, op)| root(typ, op)));
+                }
             }
         }
         Query { queries }
@@ -101,77 +472,401 @@ impl Transform<Vec<Typed>> for Mutation {
     fn transform(model: &Vec<Typed>) -> Self {
         let mut mutations: Vec<String> = vec![];
         for t in model {
+            if let Typed::Service(service) = t {
+                mutations.extend(service.mutations.iter().map(Field::generate));
+            }
             if let Typed::Type(typ) = t {
-                let mut params = vec![];
-                for attr in typ.fields.clone() {
-                    let mut param = String::new();
-                    param.push_str(attr.name.as_str());
-                    param.push_str(": ");
-                    param.push_str(attr.typ.as_str());
-                    params.push(param);
+                if typ.interface || typ.instance || typ.part || typ.event {
+                    continue;
                 }
-
-                // TODO handle id
+                // Generated keys are assigned once the object is stored.
+                let params: Vec<String> = typ
+                    .fields
+                    .iter()
+                    .filter(|f| f.key != Some(Key::Generated))
+                    .filter_map(|f| f.argument(false))
+                    .collect();
                 let query = format!("create{}({}): [{}!]", &typ.name, params.join(", "), &typ.name);
                 mutations.push(query);
+
+                // Objects with keys can be changed and removed, leaving the keys as they are.
+                let keys: Vec<String> =
+                    typ.keys().iter().filter_map(|k| k.argument(false)).collect();
+                if !keys.is_empty() {
+                    let name = &typ.name;
+                    let changes = typ.fields.iter().filter(|f| f.key.is_none());
+                    let params = [keys.clone(), changes.filter_map(|f| f.argument(true)).collect()];
+                    mutations.push(format!(
+                        "update{}({}): {}",
+                        name,
+                        params.concat().join(", "),
+                        name
+                    ));
+                    mutations.push(format!("delete{}({}): {}", name, keys.join(", "), name));
+                    let operations = typ.operations.iter();
+                    let operations = operations.filter(|(k, _)| *k == Some(OperationKind::Command));
+                    mutations.extend(operations.map(|(_, op)| root(typ, op)));
+                }
             }
         }
         Mutation { mutations }
     }
 }
 
-impl Transform<Entity> for Typed {
-    fn transform(model: &Entity) -> Self {
-        let mut fields = vec![];
-        if let Entity::Structure(struc) = model {
-            for attr in struc.attributes.clone() {
-                let mut typ = String::new();
-                let is_nullable: bool = match attr.multiplicity {
-                    Optional => true,
-                    Concrete(Discrete(num)) => num == 0,
-                    UnderUpper(Discrete(under), _upper) => under == 0,
-                    _ => false,
-                };
-
-                let mut is_list = false;
-                if let UnderUpper(_, _) = attr.multiplicity {
-                    is_list = true;
-                    typ += "[";
-                }
-                typ += attr.entity.identifier().as_str();
+/// The interfaces `struc` implements in GraphQL: the interfaces of the model and the abstract
+/// structures among its ancestors, along with everything those implement, as GraphQL demands
+/// to list them all.
+fn implemented(struc: &Structure) -> Vec<String> {
+    let ancestors = struc.ancestors().into_iter();
+    let abstracts =
+        ancestors.filter(|a| matches!(a.as_ref(), Entity::Structure(s) if s.is_abstract()));
+    let implemented = abstracts.chain(struc.implemented());
+    implemented.filter(|i| !i.has_hint("graphql", "skip")).map(|i| i.identifier()).collect()
+}
+
+/// Whether `attr` becomes an `ID`. Identifiers are opaque to clients, whatever they are made
+/// of, which is the case for generated keys and attributes annotated with `@id`.
+fn is_id(attr: &Attribute) -> bool { attr.key == Some(Key::Generated) || attr.is_annotated("id") }
 
-                if is_list {
-                    typ += "]";
+/// The field for `op`, e.g. `place(order: OrderInput!): Receipt`. Operations without a result
+/// return whether they succeeded, parameters which cannot be arguments, like unions, are left
+/// out.
+fn operation(op: &Operation) -> Field {
+    let arguments = op.parameter.iter().map(|p| Field {
+        input: input_name(&p.entity).map(|input| input + "!"),
+        ..Field::new(p.name.as_str(), reference_name(&p.entity) + "!")
+    });
+    let returns = op.returns.as_ref().map_or(String::from("Boolean"), reference_name);
+    Field {
+        description: op.docs.clone(),
+        directives: op.annotation("deprecated").map(deprecated).into_iter().collect(),
+        arguments: arguments.collect(),
+        ..Field::new(op.name.as_str(), returns)
+    }
+}
+
+/// Writes the root field for the operation `op` of the objects of `typ`, which are looked up by
+/// their keys, e.g. `vehicleCountTires(name: String!): Int`. Keys named like a parameter of the
+/// operation are prefixed with the type, e.g. `vehicleName`.
+fn root(typ: &Type, op: &Field) -> String {
+    let mut arguments: Vec<Field> = typ
+        .keys()
+        .into_iter()
+        .map(|key| {
+            if op.arguments.iter().any(|a| a.name == key.name) {
+                let name = lower_first(&typ.name) + upper_first(&key.name).as_str();
+                Field { name, ..key.clone() }
+            } else {
+                key.clone()
+            }
+        })
+        .collect();
+    arguments.extend(op.arguments.iter().cloned());
+    let name = lower_first(&typ.name) + upper_first(&op.name).as_str();
+    Field { name, arguments, ..op.clone() }.generate()
+}
+
+/// The input types the arguments of the schema take, along with the ones their fields take
+/// themselves. Each mirrors the object type of the same name, e.g. `PersonInput` for `Person`.
+fn inputs(types: &[Typed]) -> Vec<InputType> {
+    let mut pending: Vec<String> = vec![];
+    for typed in types {
+        match typed {
+            Typed::Type(typ) => {
+                // The fields of the objects are the arguments of their mutations.
+                if !(typ.interface || typ.instance || typ.part || typ.event) {
+                    pending.extend(typ.fields.iter().filter_map(|f| f.input.clone()));
                 }
+                let arguments = typ.operations.iter().flat_map(|(_, op)| op.arguments.iter());
+                pending.extend(arguments.filter_map(|a| a.input.clone()));
+            },
+            Typed::Service(service) => {
+                let operations = service.queries.iter().chain(service.mutations.iter());
+                let arguments = operations.flat_map(|op| op.arguments.iter());
+                pending.extend(arguments.filter_map(|a| a.input.clone()));
+            },
+            _ => (),
+        }
+    }
+    let mut inputs: Vec<InputType> = vec![];
+    let mut index = 0;
+    while index < pending.len() {
+        // Lists and non-null types wrap the name of the input type, e.g. `[TireInput!]!`.
+        let name = pending[index].trim_matches(|c| c == '[' || c == ']' || c == '!').to_owned();
+        index += 1;
+        if inputs.iter().any(|i| i.name == name) {
+            continue;
+        }
+        let mirrored = types.iter().find_map(|typed| match typed {
+            Typed::Type(typ) if format!("{}Input", typ.name) == name => Some(typ),
+            _ => None,
+        });
+        if let Some(typ) = mirrored {
+            pending.extend(typ.fields.iter().filter_map(|f| f.input.clone()));
+            inputs.push(InputType {
+                name,
+                description: typ.description.clone(),
+                fields: typ.fields.clone(),
+                source: typ.source.clone(),
+            });
+        }
+    }
+    inputs
+}
+
+/// The built-in scalars the fields of `entity` are made of.
+fn scalars(entity: &Entity) -> Vec<Scalar> {
+    match entity {
+        Entity::Structure(struc) if !struc.has_hint("graphql", "skip") => {
+            let attributes = struc.effective_attributes();
+            let attributes =
+                attributes.iter().filter(|a| !a.has_hint("graphql", "skip") && !is_id(a));
+            let operations = struc.effective_operations();
+            let operations = operations.iter().filter(|op| !op.has_hint("graphql", "skip"));
+            attributes
+                .map(|attr| &attr.entity)
+                .chain(operations.flat_map(|op| op.references()))
+                .filter_map(|reference| match reference.entity().as_ref() {
+                    Entity::Scalar(scalar) => Some(*scalar),
+                    _ => None,
+                })
+                .collect()
+        },
+        Entity::Service(service) if !service.has_hint("graphql", "skip") => service
+            .references()
+            .iter()
+            .filter_map(|reference| match reference.entity().as_ref() {
+                Entity::Scalar(scalar) => Some(*scalar),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
 
-                if !is_nullable {
-                    typ += "!";
+impl Transform<Entity> for Typed {
+    fn transform(model: &Entity) -> Self {
+        let mut fields = vec![];
+        match model {
+            Entity::Structure(struc) if !struc.has_hint("graphql", "skip") => {
+                // GraphQL has no inheritance, every type lists the fields it inherits.
+                for attr in struc.effective_attributes() {
+                    if attr.has_hint("graphql", "skip") {
+                        continue;
+                    }
+                    let is_nullable: bool = match attr.multiplicity {
+                        Optional => true,
+                        Concrete(Discrete(num)) => num == 0,
+                        UnderUpper(Discrete(under), _upper) => under == 0,
+                        _ => false,
+                    };
+                    let is_list = attr.multiplicity.is_list();
+                    // Wraps the name of the type of the values, e.g. `[Tire]!` for `Tire`.
+                    let wrap = |name: String| {
+                        let typ = if is_list { format!("[{}]", name) } else { name };
+                        if is_nullable {
+                            typ
+                        } else {
+                            typ + "!"
+                        }
+                    };
+                    let (typ, input) = if is_id(&attr) {
+                        (wrap(String::from("ID")), Some(wrap(String::from("ID"))))
+                    } else {
+                        (wrap(reference_name(&attr.entity)), input_name(&attr.entity).map(wrap))
+                    };
+                    let directives =
+                        attr.annotation("deprecated").map(deprecated).into_iter().collect();
+                    let default = attr.default.as_ref().map(literal);
+                    let constraint = constraint(&attr.constraints);
+                    fields.push(Field {
+                        name: attr.name,
+                        description: attr.docs,
+                        typ,
+                        directives,
+                        default,
+                        constraint,
+                        key: attr.key,
+                        input,
+                        arguments: vec![],
+                    })
                 }
-                fields.push(Field { name: attr.name, typ })
-            }
-            Typed::Type(Type { name: struc.name.clone(), fields })
-        } else {
-            Typed::None
+                let operations = struc.effective_operations();
+                let operations = operations.iter().filter(|op| !op.has_hint("graphql", "skip"));
+                Typed::Type(Type {
+                    name: struc.name.clone(),
+                    description: struc.docs.clone(),
+                    fields,
+                    interface: struc.is_interface() || struc.is_abstract(),
+                    instance: false,
+                    part: false,
+                    event: struc.is_event(),
+                    operations: operations.map(|op| (op.kind, operation(op))).collect(),
+                    implements: implemented(struc),
+                    source: None,
+                })
+            },
+            Entity::CustomScalar(custom) if !custom.has_hint("graphql", "skip") => {
+                Typed::Scalar(ScalarType {
+                    name: custom.name.clone(),
+                    description: custom.docs.clone(),
+                    source: None,
+                })
+            },
+            // Queries are the operations marked as such, everything else may change the model.
+            Entity::Service(service) if !service.has_hint("graphql", "skip") => {
+                let operations = service.operations.iter();
+                let (queries, mutations): (Vec<&Operation>, Vec<&Operation>) = operations
+                    .filter(|op| !op.has_hint("graphql", "skip"))
+                    .partition(|op| op.is_query());
+                Typed::Service(ServiceType {
+                    queries: queries.into_iter().map(operation).collect(),
+                    mutations: mutations.into_iter().map(operation).collect(),
+                })
+            },
+            Entity::Enum(enumeration) if !enumeration.has_hint("graphql", "skip") => {
+                let members = enumeration.members.iter();
+                let members = members.filter(|m| !m.has_hint("graphql", "skip"));
+                Typed::Enum(EnumType {
+                    name: enumeration.identifier.clone(),
+                    description: enumeration.docs.clone(),
+                    members: members
+                        .map(|m| EnumMemberType {
+                            name: m.name.clone(),
+                            description: m.docs.clone(),
+                            directives: m
+                                .annotation("deprecated")
+                                .map(deprecated)
+                                .into_iter()
+                                .collect(),
+                        })
+                        .collect(),
+                    source: None,
+                })
+            },
+            Entity::Union(union) if !union.has_hint("graphql", "skip") => {
+                let members = union.members.iter().map(|m| m.entity());
+                let members = members.filter(|m| !m.has_hint("graphql", "skip"));
+                Typed::Union(UnionType {
+                    name: union.name.clone(),
+                    description: union.docs.clone(),
+                    members: members.map(|m| m.identifier()).collect(),
+                    source: None,
+                })
+            },
+            _ => Typed::None,
         }
     }
 }
 
 impl Field {
+    /// A field of type `typ` without a description, directives or arguments, which takes values
+    /// of the same type as an argument.
+    fn new(name: &str, typ: String) -> Field {
+        Field {
+            name: String::from(name),
+            description: None,
+            input: Some(typ.clone()),
+            typ,
+            directives: vec![],
+            default: None,
+            constraint: None,
+            key: None,
+            arguments: vec![],
+        }
+    }
+
+    /// Writes the field as an argument of a root field or a field of an input type, unless it
+    /// cannot be one. Arguments of partial updates are `nullable` and have no default, leaving
+    /// out an argument keeps the value of the field.
+    fn argument(&self, nullable: bool) -> Option<String> {
+        let mut argument = format!("{}: {}", self.name, self.input.as_ref()?);
+        if nullable {
+            argument = argument.trim_end_matches('!').to_owned();
+        } else if let Some(default) = &self.default {
+            argument += format!(" = {}", default).as_str();
+        }
+        if let Some(constraint) = &self.constraint {
+            argument += format!(" {}", constraint).as_str();
+        }
+        Some(argument)
+    }
+
     fn generate(&self) -> String {
         let mut buffer = Buffer::default();
+        describe(&mut buffer, &self.description);
         buffer += self.name.as_str();
+        if !self.arguments.is_empty() {
+            let arguments: Vec<String> =
+                self.arguments.iter().filter_map(|a| a.argument(false)).collect();
+            buffer += format!("({})", arguments.join(", ")).as_str();
+        }
         buffer += ": ";
         buffer += self.typ.as_str();
+        for directive in self.directives.iter().chain(self.constraint.iter()) {
+            buffer += " ";
+            buffer += directive.as_str();
+        }
 
         buffer.flush()
     }
 }
 
+/// Writes `value` as a GraphQL value.
+fn literal(value: &Value) -> String {
+    match value {
+        Value::String(string) => format!("{:?}", string),
+        value => value.to_string(),
+    }
+}
+
+/// The `@constraint` directive for the constraints of an attribute, which is declared by
+/// `CONSTRAINT_DIRECTIVE`.
+fn constraint(constraints: &[Constraint]) -> Option<String> {
+    if constraints.is_empty() {
+        return None;
+    }
+    let arguments: Vec<String> =
+        constraints.iter().map(|c| format!("{}: {}", c.kind, literal(&c.value))).collect();
+    Some(format!("@constraint({})", arguments.join(", ")))
+}
+
+const CONSTRAINT_DIRECTIVE: &str = "directive @constraint(min: Float, max: Float, minLength: Int, \
+                                    maxLength: Int, pattern: String) on FIELD_DEFINITION | \
+                                    ARGUMENT_DEFINITION | INPUT_FIELD_DEFINITION";
+
+/// The `@deprecated` directive for an element annotated with `@deprecated("reason")`.
+fn deprecated(annotation: &Annotation) -> String {
+    match &annotation.value {
+        Some(reason) => format!("@deprecated(reason: {:?})", reason),
+        None => String::from("@deprecated"),
+    }
+}
+
+/// Writes the doc comments of the model as a block string description.
+fn describe(buffer: &mut Buffer, description: &Option<String>) {
+    if let Some(description) = description {
+        *buffer += "\"\"\"";
+        for line in description.lines() {
+            buffer.new_line();
+            *buffer += line.replace("\"\"\"", "\\\"\"\"").as_str();
+        }
+        buffer.new_line();
+        *buffer += "\"\"\"";
+        buffer.new_line();
+    }
+}
+
 impl Typed {
     fn generate(&self) -> Option<GeneratedCode> {
         match self {
             Typed::Type(t) => Some(t.generate()),
-            Typed::None => None,
+            Typed::Scalar(s) => Some(s.generate()),
+            Typed::Union(u) => Some(u.generate()),
+            Typed::Enum(e) => Some(e.generate()),
+            Typed::Input(i) => Some(i.generate()),
+            // Services only contribute fields to the root types.
+            Typed::Service(_) | Typed::None => None,
         }
     }
 }
@@ -179,11 +874,21 @@ impl Typed {
 impl Generate for Type {
     fn generate(&self) -> GeneratedCode {
         let mut buffer = Buffer::default();
-        buffer += "type ";
+        if let Some(source) = &self.source {
+            buffer += "# declared at ";
+            buffer += source.as_str();
+            buffer.new_line();
+        }
+        describe(&mut buffer, &self.description);
+        buffer += if self.interface { "interface " } else { "type " };
         buffer += self.name.as_str();
+        if !self.implements.is_empty() {
+            buffer += " implements ";
+            buffer += self.implements.join(" & ").as_str();
+        }
         buffer += " {";
         buffer.indent();
-        for field in self.fields.clone() {
+        for field in self.fields.iter().chain(self.operations.iter().map(|(_, op)| op)) {
             buffer.new_line();
             buffer += field.generate().as_str();
             buffer += ",";
@@ -196,6 +901,99 @@ impl Generate for Type {
     }
 }
 
+impl Generate for InputType {
+    fn generate(&self) -> GeneratedCode {
+        let mut buffer = Buffer::default();
+        if let Some(source) = &self.source {
+            buffer += "# declared at ";
+            buffer += source.as_str();
+            buffer.new_line();
+        }
+        describe(&mut buffer, &self.description);
+        buffer += "input ";
+        buffer += self.name.as_str();
+        buffer += " {";
+        buffer.indent();
+        for field in self.fields.iter() {
+            if let Some(argument) = field.argument(false) {
+                buffer.new_line();
+                describe(&mut buffer, &field.description);
+                buffer += argument.as_str();
+                buffer += ",";
+            }
+        }
+        buffer.unindent();
+        buffer.new_line();
+        buffer += "}";
+
+        GeneratedCode { code: buffer.flush(), id: self.name.clone() }
+    }
+}
+
+impl Generate for ScalarType {
+    fn generate(&self) -> GeneratedCode {
+        let mut buffer = Buffer::default();
+        if let Some(source) = &self.source {
+            buffer += "# declared at ";
+            buffer += source.as_str();
+            buffer.new_line();
+        }
+        describe(&mut buffer, &self.description);
+        buffer += "scalar ";
+        buffer += self.name.as_str();
+
+        GeneratedCode { code: buffer.flush(), id: self.name.clone() }
+    }
+}
+
+impl Generate for UnionType {
+    fn generate(&self) -> GeneratedCode {
+        let mut buffer = Buffer::default();
+        if let Some(source) = &self.source {
+            buffer += "# declared at ";
+            buffer += source.as_str();
+            buffer.new_line();
+        }
+        describe(&mut buffer, &self.description);
+        buffer += "union ";
+        buffer += self.name.as_str();
+        buffer += " = ";
+        buffer += self.members.join(" | ").as_str();
+
+        GeneratedCode { code: buffer.flush(), id: self.name.clone() }
+    }
+}
+
+impl Generate for EnumType {
+    fn generate(&self) -> GeneratedCode {
+        let mut buffer = Buffer::default();
+        if let Some(source) = &self.source {
+            buffer += "# declared at ";
+            buffer += source.as_str();
+            buffer.new_line();
+        }
+        describe(&mut buffer, &self.description);
+        buffer += "enum ";
+        buffer += self.name.as_str();
+        buffer += " {";
+        buffer.indent();
+        for member in self.members.iter() {
+            buffer.new_line();
+            describe(&mut buffer, &member.description);
+            buffer += member.name.as_str();
+            for directive in member.directives.iter() {
+                buffer += " ";
+                buffer += directive.as_str();
+            }
+        }
+        buffer.unindent();
+        buffer.new_line();
+        buffer += "}";
+
+        GeneratedCode { code: buffer.flush(), id: self.name.clone() }
+    }
+}
+
 impl Generate for Schema {
     fn generate(&self) -> GeneratedCode {
         let mut buffer = Buffer::default();
@@ -205,6 +1003,10 @@ impl Generate for Schema {
         buffer += "query: Query,";
         buffer.new_line();
         buffer += "mutation: Mutation,";
+        if self.subscription.is_some() {
+            buffer.new_line();
+            buffer += "subscription: Subscription,";
+        }
         buffer.unindent();
         buffer.new_line();
         buffer += "}";
@@ -213,6 +1015,10 @@ impl Generate for Schema {
         buffer += self.mutation.generate().as_str();
         buffer.new_line();
         buffer += self.query.generate().as_str();
+        if let Some(subscription) = &self.subscription {
+            buffer.new_line();
+            buffer += subscription.generate().as_str();
+        }
 
         GeneratedCode { code: buffer.flush(), id: "schema".to_string() }
     }
@@ -253,10 +1059,37 @@ impl Mutation {
     }
 }
 
+impl Subscription {
+    fn generate(&self) -> String {
+        let mut buffer = Buffer::default();
+        buffer += "type Subscription {";
+        buffer.indent();
+        for subscription in self.subscriptions.iter() {
+            buffer.new_line();
+            buffer += subscription.as_str();
+            buffer += ",";
+        }
+        buffer.unindent();
+        buffer.new_line();
+        buffer += "}";
+        buffer.flush()
+    }
+}
+
 impl Document {
     fn generate(&self) -> CodePage {
         let mut codepage = CodePage::default("#");
 
+        let constrained = self.types.iter().any(|t| match t {
+            Typed::Type(typ) => typ.fields.iter().any(|f| f.constraint.is_some()),
+            _ => false,
+        });
+        if constrained {
+            let directive = String::from(CONSTRAINT_DIRECTIVE);
+            let id = String::from("constraint");
+            codepage.add(GeneratedCode { code: directive, id }.to_fragment());
+        }
+
         for typ in self.types.clone() {
             if let Some(generated) = typ.generate() {
                 codepage.add(generated.to_fragment());
@@ -266,3 +1099,196 @@ impl Document {
         codepage
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::graphql::Document;
+    use ast::Package;
+    use generator::Transform;
+    use graphql_parser::schema::{Definition, EnumType, Type, TypeDefinition};
+    use parser::{parse, parse_from_file};
+    use util::code::CodeFragment;
+
+    /// Generates the schema of `package`, leaving out the comments marking the fragments.
+    fn generate(package: &Package) -> String {
+        let page = Document::transform(package).generate();
+        let fragments: Vec<String> = page
+            .fragments
+            .into_iter()
+            .filter_map(|fragment| match fragment {
+                CodeFragment::Generated(generated) => Some(generated.code),
+                _ => None,
+            })
+            .collect();
+        fragments.join("\n\n")
+    }
+
+    /// The name of the type `typ` is a list or a non-null version of.
+    fn named(typ: &Type) -> String {
+        match typ {
+            Type::NamedType(name) => name.clone(),
+            Type::ListType(inner) | Type::NonNullType(inner) => named(inner),
+        }
+    }
+
+    /// Parses `schema` and checks that every type it references is declared, and that arguments
+    /// and input fields only reference input types, scalars and enums.
+    fn validate(schema: &str) -> Vec<Definition> {
+        let document = graphql_parser::parse_schema(schema).expect("Should be a valid schema");
+        let mut declared: Vec<String> =
+            ["Int", "Float", "String", "Boolean", "ID"].iter().map(|s| s.to_string()).collect();
+        let mut inputs = declared.clone();
+        let mut referenced: Vec<String> = vec![];
+        let mut arguments: Vec<String> = vec![];
+        for definition in document.definitions.iter() {
+            match definition {
+                Definition::SchemaDefinition(schema) => {
+                    let roots = [&schema.query, &schema.mutation, &schema.subscription];
+                    referenced.extend(roots.iter().filter_map(|root| (*root).clone()));
+                },
+                Definition::TypeDefinition(TypeDefinition::Object(object)) => {
+                    declared.push(object.name.clone());
+                    referenced.extend(object.implements_interfaces.iter().cloned());
+                    for field in object.fields.iter() {
+                        referenced.push(named(&field.field_type));
+                        arguments.extend(field.arguments.iter().map(|a| named(&a.value_type)));
+                    }
+                },
+                Definition::TypeDefinition(TypeDefinition::Interface(interface)) => {
+                    declared.push(interface.name.clone());
+                    for field in interface.fields.iter() {
+                        referenced.push(named(&field.field_type));
+                        arguments.extend(field.arguments.iter().map(|a| named(&a.value_type)));
+                    }
+                },
+                Definition::TypeDefinition(TypeDefinition::InputObject(input)) => {
+                    declared.push(input.name.clone());
+                    inputs.push(input.name.clone());
+                    arguments.extend(input.fields.iter().map(|f| named(&f.value_type)));
+                },
+                Definition::TypeDefinition(TypeDefinition::Union(union)) => {
+                    declared.push(union.name.clone());
+                    referenced.extend(union.types.iter().cloned());
+                },
+                Definition::TypeDefinition(TypeDefinition::Enum(enumeration)) => {
+                    declared.push(enumeration.name.clone());
+                    inputs.push(enumeration.name.clone());
+                },
+                Definition::TypeDefinition(TypeDefinition::Scalar(scalar)) => {
+                    declared.push(scalar.name.clone());
+                    inputs.push(scalar.name.clone());
+                },
+                _ => (),
+            }
+        }
+        for name in referenced {
+            assert!(declared.contains(&name), "`{}` is referenced but not declared", name);
+        }
+        for name in arguments {
+            assert!(inputs.contains(&name), "`{}` is taken as an argument but not an input", name);
+        }
+        document.definitions
+    }
+
+    fn enumeration<'a>(definitions: &'a [Definition], name: &str) -> &'a EnumType {
+        let found = definitions.iter().find_map(|definition| match definition {
+            Definition::TypeDefinition(TypeDefinition::Enum(e)) if e.name == name => Some(e),
+            _ => None,
+        });
+        found.unwrap_or_else(|| panic!("`{}` should be declared", name))
+    }
+
+    /// The fields of the object type, interface or input type called `name`, with their arguments.
+    fn fields(definitions: &[Definition], name: &str) -> Vec<String> {
+        let found = definitions.iter().find_map(|definition| match definition {
+            Definition::TypeDefinition(TypeDefinition::Object(o)) if o.name == name => {
+                Some(o.fields.iter().map(|f| f.to_string()).collect())
+            },
+            Definition::TypeDefinition(TypeDefinition::Interface(i)) if i.name == name => {
+                Some(i.fields.iter().map(|f| f.to_string()).collect())
+            },
+            Definition::TypeDefinition(TypeDefinition::InputObject(i)) if i.name == name => {
+                Some(i.fields.iter().map(|f| f.to_string()).collect())
+            },
+            _ => None,
+        });
+        let fields: Vec<String> = found.unwrap_or_else(|| panic!("`{}` should be declared", name));
+        fields.iter().map(|f| f.trim().to_owned()).collect()
+    }
+
+    #[test]
+    fn test_example() {
+        let package = parse_from_file("../parser/test/example.pakken").expect("Should have parsed");
+        let definitions = validate(generate(&package).as_str());
+        let members = &enumeration(&definitions, "Enum").values;
+        let names: Vec<&str> = members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["value1", "value2"]);
+        for vehicle in ["Vehicle", "Bike", "Car"].iter() {
+            assert!(fields(&definitions, vehicle).contains(&String::from("countTires: Int")));
+        }
+        let rides = fields(&definitions, "Bike");
+        assert!(rides.contains(&String::from("ride(person: PersonInput!): Boolean")));
+        assert!(!fields(&definitions, "PersonInput").is_empty());
+    }
+
+    #[test]
+    fn test_enums() {
+        let code = "org.a {\n    /// How urgent.\n    Priority { unknown = -1, high }\n    Color \
+                    {\n  /// Pure red.\n        red = \"#f00\",\n        @deprecated(\"use red\") \
+                    green,\n        @graphql(skip) blue\n    }\n    A { p: Priority = high, c: \
+                    Map<Color, Integer> }\n}";
+        let package = parse(code).expect("Should have parsed");
+        let schema = generate(&package);
+        let definitions = validate(schema.as_str());
+        let priority = enumeration(&definitions, "Priority");
+        // The parser keeps the line breaks of block strings.
+        assert_eq!(priority.description.as_deref().map(str::trim), Some("How urgent."));
+        let color = enumeration(&definitions, "Color");
+        let names: Vec<&str> = color.values.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["red", "green"]);
+        assert_eq!(color.values[0].description.as_deref().map(str::trim), Some("Pure red."));
+        assert_eq!(color.values[1].directives[0].name, "deprecated");
+        assert!(schema.contains("createA(p: Priority! = high, c: [ColorToIntEntryInput!]!)"));
+    }
+
+    #[test]
+    fn test_operations() {
+        let code = "org.a {\n    Address { street: String }\n    Order {\n        key number: \
+                    String,\n        lines: Line [*],\n        found: Found?,\n        query \
+                    total(currency: String): Integer,\n        command ship(to: Address),\n        \
+                    /// Splits the order.\n        split(line: Line): Order\n    }\n    Line { \
+                    product: String }\n    service Checkout {\n        query basket(order: \
+                    Order): Order,\n        command place(order: Order): Order,\n        command \
+                    keep(found: Found, order: Order)\n    }\n    union Found = Address | Line\n}";
+        let package = parse(code).expect("Should have parsed");
+        let definitions = validate(generate(&package).as_str());
+        let order = fields(&definitions, "Order");
+        assert!(order.contains(&String::from("total(currency: String!): Int")));
+        assert!(order.contains(&String::from("ship(to: AddressInput!): Boolean")));
+        assert!(order.iter().any(|f| f.ends_with("split(line: LineInput!): Order")));
+        assert!(fields(&definitions, "OrderInput").contains(&String::from("lines: [LineInput]")));
+        // Only the operations marked as queries or commands are root fields.
+        let query = fields(&definitions, "Query");
+        assert!(
+            query.contains(&String::from("orderTotal(number: String!, currency: String!): Int"))
+        );
+        assert!(query.contains(&String::from("basket(order: OrderInput!): Order")));
+        let mutation = fields(&definitions, "Mutation");
+        assert!(mutation
+            .contains(&String::from("orderShip(number: String!, to: AddressInput!): Boolean")));
+        assert!(!query.iter().chain(mutation.iter()).any(|f| f.contains("Split")));
+        // Unions have no input types, so they are left out of the arguments.
+        assert!(mutation.contains(&String::from("keep(order: OrderInput!): Boolean")));
+        assert!(!fields(&definitions, "OrderInput").iter().any(|f| f.starts_with("found")));
+        assert!(mutation.iter().any(|f| f.starts_with("createOrder(") && !f.contains("found")));
+    }
+
+    #[test]
+    fn test_operation_named_like_key() {
+        let code = "org.a { A { key name: String, query lookup(name: String): A } }";
+        let package = parse(code).expect("Should have parsed");
+        let definitions = validate(generate(&package).as_str());
+        let query = fields(&definitions, "Query");
+        assert!(query.contains(&String::from("aLookup(aName: String!, name: String!): A")));
+    }
+}
@@ -1,4 +1,7 @@
-use ast::{Entity, Identifying, Namespace, Scalar, Structure};
+use ast::{
+    Annotated, Attribute, Constraint, ConstraintKind, Entity, EnumValue, Identifying, Multiplicity,
+    Operation, Package, Reference, Scalar, Service, Structure, StructureKind, Value,
+};
 use generator::Transform;
 use parser::parse_from_file;
 use util::buffer::Buffer;
@@ -13,9 +16,11 @@ pub struct TypeScriptTarget {}
 impl Target for TypeScriptTarget {
     fn name(&self) -> &'static str { "typescript" }
 
+    fn annotations(&self) -> &'static [&'static str] { &["readonly", "deprecated", "typescript"] }
+
     fn generate_from(&self, model: Model) -> PakResult<FileStructure> {
-        let namespace = parse_from_file(model.path.as_path())?;
-        let transformed = Declaration::transform(&namespace);
+        let package = parse_from_file(model.path.as_path())?;
+        let transformed = Declaration::transform(&package);
         let schema = transformed.generate();
         let file_structure =
             FileStructure::Dir("typescript".to_owned(), vec![FileStructure::File(
@@ -35,34 +40,121 @@ struct Declaration {
 enum Typed {
     Class(Class),
     Interface(Interface),
-    Scalar(String),
+    Alias(Alias),
+    Enumeration(Enumeration),
     Vec(Vec<Typed>),
     None,
 }
 
 impl Typed {
-    fn identifier(&self) -> String {
+    fn set_source(&mut self, source: Option<String>) {
         match self {
-            Typed::Class(class) => class.name.clone(),
-            Typed::Scalar(scalar) => scalar.clone(),
-            Typed::Interface(interface) => interface.name.clone(),
-            Typed::Vec(vec) => vec.first().expect("Should have at least one member!").identifier(),
-            _ => panic!("Could not get identifier for {:?}", self),
+            Typed::Class(class) => class.source = source,
+            Typed::Interface(interface) => interface.source = source,
+            Typed::Alias(alias) => alias.source = source,
+            Typed::Enumeration(enumeration) => enumeration.source = source,
+            _ => (),
         }
     }
+}
 
-    fn is_some(&self) -> bool {
-        match self {
-            Typed::Class(_) => true,
-            Typed::Scalar(_) => true,
-            _ => false,
-        }
+/// The TypeScript types of the built-in scalars.
+const SCALARS: [(Scalar, &str); 9] = [
+    (Scalar::String, "string"),
+    (Scalar::Integer, "number"),
+    (Scalar::Double, "number"),
+    (Scalar::Boolean, "boolean"),
+    (Scalar::Character, "string"),
+    (Scalar::Date, "Date"),
+    (Scalar::DateTime, "Date"),
+    (Scalar::Uuid, "string"),
+    // Numbers would lose the precision of decimals.
+    (Scalar::Decimal, "string"),
+];
+
+/// The name of the TypeScript type of `entity`.
+fn type_name(entity: &Entity) -> String {
+    match entity {
+        Entity::Scalar(scalar) => {
+            let found = SCALARS.iter().find(|(s, _)| s == scalar);
+            found.map(|(_, name)| *name).expect("Every scalar should be mapped").to_owned()
+        },
+        // Maps become records, e.g. `Record<string, number>`.
+        Entity::Map => String::from("Record"),
+        entity => entity.identifier(),
     }
 }
 
+/// The TypeScript type `reference` refers to, with the type arguments of a generic structure or
+/// a map, e.g. `Page<Order>`.
+fn reference_name(reference: &Reference) -> String {
+    let name = type_name(reference.entity().as_ref());
+    if reference.arguments().is_empty() {
+        return name;
+    }
+    let arguments: Vec<String> = reference.arguments().iter().map(reference_name).collect();
+    format!("{}<{}>", name, arguments.join(", "))
+}
+
+/// The type parameters of a generic structure, e.g. `<T>` of `Page<T>`, or nothing.
+fn parameters(struc: &Structure) -> String {
+    if !struc.is_generic() {
+        return String::new();
+    }
+    let parameters: Vec<String> = struc.parameters.iter().map(|p| p.identifier()).collect();
+    format!("<{}>", parameters.join(", "))
+}
+
+/// The interface of an event, whose attributes cannot change once it happened. The
+/// `__typename` tells the events apart in the unions of events.
+fn event(struc: &Structure) -> Interface {
+    let mut attributes = vec![format!("readonly __typename: {:?}", struc.name)];
+    for attr in struc.attributes.iter().filter(|attr| !attr.has_hint("typescript", "skip")) {
+        let mut buf = Buffer::default();
+        buf += tsdoc(&attr.docs, attr).as_str();
+        buf += format!("readonly {}: {}", attr.name, reference_name(&attr.entity)).as_str();
+        attributes.push(buf.flush());
+    }
+    Interface {
+        name: struc.name.clone(),
+        parameters: String::new(),
+        docs: tsdoc(&struc.docs, struc),
+        source: None,
+        extends: None,
+        attributes,
+        operations: vec![],
+    }
+}
+
+/// A type alias for a scalar or a union declared in the model, e.g. `type Email = string`.
+#[derive(Debug)]
+struct Alias {
+    name: String,
+    /// The rendered TSDoc.
+    docs: String,
+    source: Option<String>,
+    typ: String,
+}
+
+/// An `export enum` whose members keep the values of the model. Members of string enums
+/// without a value stand for their name, the others are numbered by TypeScript.
+#[derive(Debug)]
+struct Enumeration {
+    name: String,
+    /// The rendered TSDoc.
+    docs: String,
+    source: Option<String>,
+    members: Vec<String>,
+}
+
 #[derive(Debug)]
 struct Interface {
     name: String,
+    /// The rendered type parameters, e.g. `<T>`.
+    parameters: String,
+    /// The rendered TSDoc.
+    docs: String,
+    source: Option<String>,
     extends: Option<Vec<String>>,
     attributes: Vec<String>,
     operations: Vec<String>,
@@ -71,6 +163,13 @@ struct Interface {
 #[derive(Debug)]
 struct Class {
     name: String,
+    /// The rendered type parameters, e.g. `<T>`.
+    parameters: String,
+    /// The rendered TSDoc.
+    docs: String,
+    source: Option<String>,
+    /// Whether the class is `abstract`, which only abstract structures are.
+    is_abstract: bool,
     extends: Option<String>,
     implements: Option<Vec<String>>,
     attributes: Vec<String>,
@@ -80,18 +179,122 @@ struct Class {
 impl Transform<Entity> for Typed {
     fn transform(model: &Entity) -> Self {
         match model {
-            Entity::Structure(struc) => {
+            Entity::Structure(struc)
+                if struc.is_event() && !struc.has_hint("typescript", "skip") =>
+            {
+                Typed::Interface(event(struc))
+            },
+            Entity::Structure(struc)
+                if struc.is_interface() && !struc.has_hint("typescript", "skip") =>
+            {
+                let mut vec = vec![Typed::Interface(Interface::transform(struc))];
+                vec.extend(key_alias(struc).map(Typed::Alias));
+                Typed::Vec(vec)
+            },
+            Entity::Structure(struc)
+                if !struc.is_interface() && !struc.has_hint("typescript", "skip") =>
+            {
                 let mut vec = vec![];
                 vec.push(Typed::Class(Class::transform(struc)));
                 vec.push(Typed::Interface(Interface::transform(struc)));
+                vec.extend(key_alias(struc).map(Typed::Alias));
+                Typed::Vec(vec)
+            },
+            Entity::Service(service) if !service.has_hint("typescript", "skip") => {
+                let mut vec = vec![Typed::Interface(Interface::transform(service))];
+                let mut events: Vec<String> = vec![];
+                for op in service.operations.iter().filter(|op| !op.has_hint("typescript", "skip"))
+                {
+                    for event in op.emits.iter().map(|e| e.entity()) {
+                        if !event.has_hint("typescript", "skip")
+                            && !events.contains(&event.identifier())
+                        {
+                            events.push(event.identifier());
+                        }
+                    }
+                }
+                if !events.is_empty() {
+                    vec.push(Typed::Alias(Alias {
+                        name: format!("{}Event", service.name),
+                        docs: format!(
+                            "/** The events the operations of {} emit. */\n",
+                            service.name
+                        ),
+                        source: None,
+                        typ: events.join(" | "),
+                    }));
+                }
                 Typed::Vec(vec)
             },
-            Entity::Scalar(scalar) => match scalar {
-                Scalar::String => Typed::Scalar("string".to_owned()),
-                Scalar::Double => Typed::Scalar("number".to_owned()),
-                Scalar::Integer => Typed::Scalar("number".to_owned()),
-                Scalar::Character => Typed::Scalar("char".to_owned()),
-                Scalar::Boolean => Typed::Scalar("bool".to_owned()),
+            Entity::Enum(enumeration) if !enumeration.has_hint("typescript", "skip") => {
+                let strings = enumeration
+                    .members
+                    .iter()
+                    .any(|m| matches!(m.value, Some(EnumValue::String(_))));
+                let mut members = vec![];
+                // The value of the member before, which the next member without a value follows.
+                let mut previous: Option<i64> = None;
+                let mut skipped = false;
+                for member in enumeration.members.iter() {
+                    let number = match &member.value {
+                        Some(EnumValue::Integer(integer)) => Some(*integer),
+                        Some(EnumValue::String(_)) => None,
+                        None => previous.map_or(Some(0), |p| p.checked_add(1)),
+                    };
+                    previous = number;
+                    if member.has_hint("typescript", "skip") {
+                        skipped = true;
+                        continue;
+                    }
+                    let mut buf = Buffer::default();
+                    buf += tsdoc(&member.docs, member).as_str();
+                    buf += member.name.as_str();
+                    match &member.value {
+                        Some(EnumValue::String(string)) => {
+                            buf += format!(" = {:?}", string).as_str()
+                        },
+                        Some(EnumValue::Integer(integer)) => {
+                            buf += format!(" = {}", integer).as_str()
+                        },
+                        None if strings => buf += format!(" = {:?}", member.name).as_str(),
+                        // Members after a skipped one would be numbered as if it was not there.
+                        None if skipped => {
+                            if let Some(number) = number {
+                                buf += format!(" = {}", number).as_str()
+                            }
+                        },
+                        None => (),
+                    }
+                    members.push(buf.flush());
+                }
+                Typed::Enumeration(Enumeration {
+                    name: enumeration.identifier.clone(),
+                    docs: tsdoc(&enumeration.docs, enumeration),
+                    source: None,
+                    members,
+                })
+            },
+            Entity::CustomScalar(custom) if !custom.has_hint("typescript", "skip") => {
+                Typed::Alias(Alias {
+                    name: custom.name.clone(),
+                    docs: tsdoc(&custom.docs, model),
+                    source: None,
+                    typ: type_name(custom.base.entity().as_ref()),
+                })
+            },
+            // A discriminated union, the members are told apart by their `__typename`.
+            Entity::Union(union) if !union.has_hint("typescript", "skip") => {
+                let members = union.members.iter().map(|m| m.entity());
+                let members: Vec<String> = members
+                    .filter(|m| !m.has_hint("typescript", "skip"))
+                    .map(|m| format!("({0} & {{ __typename: {0:?} }})", m.identifier()))
+                    .collect();
+                Typed::Alias(Alias {
+                    name: union.name.clone(),
+                    docs: tsdoc(&union.docs, model),
+                    source: None,
+                    typ: members.join(" | "),
+                })
             },
             _ => Typed::None,
         }
@@ -106,6 +309,10 @@ impl Declaration {
                 fragments.push(class.generate().to_fragment());
             } else if let Typed::Interface(interface) = tp {
                 fragments.push(interface.generate().to_fragment())
+            } else if let Typed::Alias(alias) = tp {
+                fragments.push(alias.generate().to_fragment())
+            } else if let Typed::Enumeration(enumeration) = tp {
+                fragments.push(enumeration.generate().to_fragment())
             }
         }
         CodePage { comment_string: "//", fragments }
@@ -115,8 +322,15 @@ impl Declaration {
 impl Generate for Class {
     fn generate(&self) -> GeneratedCode {
         let mut buf = Buffer::default();
-        buf += "export class ";
+        if let Some(source) = &self.source {
+            buf += "// declared at ";
+            buf += source.as_str();
+            buf.new_line();
+        }
+        buf += self.docs.as_str();
+        buf += if self.is_abstract { "export abstract class " } else { "export class " };
         buf += self.name.as_str();
+        buf += self.parameters.as_str();
         if let Some(extends) = &self.extends {
             buf += " extends ";
             buf += extends.as_str();
@@ -144,11 +358,59 @@ impl Generate for Class {
     }
 }
 
+impl Generate for Alias {
+    fn generate(&self) -> GeneratedCode {
+        let mut buf = Buffer::default();
+        if let Some(source) = &self.source {
+            buf += "// declared at ";
+            buf += source.as_str();
+            buf.new_line();
+        }
+        buf += self.docs.as_str();
+        buf += format!("export type {} = {};", self.name, self.typ).as_str();
+
+        GeneratedCode { id: self.name.clone(), code: buf.flush() }
+    }
+}
+
+impl Generate for Enumeration {
+    fn generate(&self) -> GeneratedCode {
+        let mut buf = Buffer::default();
+        if let Some(source) = &self.source {
+            buf += "// declared at ";
+            buf += source.as_str();
+            buf.new_line();
+        }
+        buf += self.docs.as_str();
+        buf += "export enum ";
+        buf += self.name.as_str();
+        buf += " {";
+        buf.indent();
+        for member in &self.members {
+            buf.new_line();
+            buf += member.as_str();
+            buf += ",";
+        }
+        buf.unindent();
+        buf.new_line();
+        buf += "}";
+
+        GeneratedCode { id: self.name.clone(), code: buf.flush() }
+    }
+}
+
 impl Generate for Interface {
     fn generate(&self) -> GeneratedCode {
         let mut buf = Buffer::default();
+        if let Some(source) = &self.source {
+            buf += "// declared at ";
+            buf += source.as_str();
+            buf.new_line();
+        }
+        buf += self.docs.as_str();
         buf += "export interface ";
         buf += self.name.as_str();
+        buf += self.parameters.as_str();
         if let Some(extends) = &self.extends {
             buf += " extends ";
             buf += extends.iter().map(|s| &**s).collect::<Vec<&str>>().join(", ").as_str();
@@ -172,20 +434,204 @@ impl Generate for Interface {
     }
 }
 
-impl Transform<Namespace> for Declaration {
-    fn transform(model: &Namespace) -> Self {
+/// Writes the `validate` method of a class, which checks the constraints of `attributes`.
+fn validate(attributes: &[&Attribute]) -> String {
+    let mut buf = Buffer::default();
+    buf += "/** Returns the constraints of the model the attributes violate. */";
+    buf.new_line();
+    buf += "public validate(): string[] {";
+    buf.indent();
+    buf.new_line();
+    buf += "const violations: string[] = [];";
+    for attr in attributes {
+        // Decimals are strings, which are compared with the bounds as numbers.
+        let decimal = attr.entity.entity().scalar() == Some(Scalar::Decimal);
+        let number = |value: &str| {
+            if decimal {
+                format!("Number({})", value)
+            } else {
+                value.to_owned()
+            }
+        };
+        let this = format!("this.{}", attr.name);
+        for constraint in &attr.constraints {
+            let condition = match attr.multiplicity {
+                Multiplicity::Single => violation(constraint, number(this.as_str()).as_str()),
+                Multiplicity::Optional => {
+                    let violated = violation(constraint, number(this.as_str()).as_str());
+                    format!("{} != null && {}", this, violated)
+                },
+                _ => {
+                    let violated = violation(constraint, number("value").as_str());
+                    format!("{}.some(value => {})", this, violated)
+                },
+            };
+            let message = format!("{} violates {}", attr.name, constraint);
+            buf.new_line();
+            buf += format!("if ({}) {{", condition).as_str();
+            buf.indent();
+            buf.new_line();
+            buf += format!("violations.push({:?});", message).as_str();
+            buf.unindent();
+            buf.new_line();
+            buf += "}";
+        }
+    }
+    buf.new_line();
+    buf += "return violations;";
+    buf.unindent();
+    buf.new_line();
+    buf += "}";
+    buf.new_line();
+    buf.flush()
+}
+
+/// The condition under which `value` violates `constraint`.
+fn violation(constraint: &Constraint, value: &str) -> String {
+    let bound = match &constraint.value {
+        Value::String(pattern) => format!("{:?}", pattern),
+        bound => bound.to_string(),
+    };
+    match constraint.kind {
+        ConstraintKind::Min => format!("{} < {}", value, bound),
+        ConstraintKind::Max => format!("{} > {}", value, bound),
+        ConstraintKind::MinLength => format!("{}.length < {}", value, bound),
+        ConstraintKind::MaxLength => format!("{}.length > {}", value, bound),
+        ConstraintKind::Pattern => format!("!new RegExp({}).test({})", bound, value),
+    }
+}
+
+/// The key attributes of `struc`, leaving out skipped ones.
+fn keys(struc: &Structure) -> Vec<Attribute> {
+    struc.keys().into_iter().filter(|k| !k.has_hint("typescript", "skip")).collect()
+}
+
+/// The type of the keys identifying the instances of `struc`, e.g.
+/// `type OrderKey = Pick<IOrder, "number">`, if it has any.
+fn key_alias(struc: &Structure) -> Option<Alias> {
+    let keys: Vec<String> = keys(struc).iter().map(|k| format!("{:?}", k.name)).collect();
+    if keys.is_empty() {
+        return None;
+    }
+    let prefix = if struc.is_interface() { "" } else { "I" };
+    let parameters = parameters(struc);
+    Some(Alias {
+        name: format!("{}Key{}", struc.name, parameters),
+        docs: format!("/** The keys identifying an instance of `{}`. */\n", struc.name),
+        source: None,
+        typ: format!("Pick<{}{}{}, {}>", prefix, struc.name, parameters, keys.join(" | ")),
+    })
+}
+
+/// Writes the static `findById` method of the class of `struc`, which looks up an instance by
+/// its keys, if it has any.
+fn find_by_id(struc: &Structure) -> Option<String> {
+    let keys = keys(struc);
+    if keys.is_empty() {
+        return None;
+    }
+    // Dates are objects, which are only equal to themselves.
+    let conditions: Vec<String> = keys
+        .iter()
+        .map(|k| match type_name(k.entity.entity().as_ref()).as_str() {
+            "Date" => format!("item.{0}.getTime() === key.{0}.getTime()", k.name),
+            _ => format!("item.{0} === key.{0}", k.name),
+        })
+        .collect();
+    let (name, parameters) = (&struc.name, parameters(struc));
+    let mut buf = Buffer::default();
+    buf += "/** Finds the instance of `items` with the keys of `key`. */";
+    buf.new_line();
+    buf += format!(
+        "public static findById{1}(items: {0}{1}[], key: {0}Key{1}): {0}{1} | undefined {{",
+        name, parameters
+    )
+    .as_str();
+    buf.indent();
+    buf.new_line();
+    buf += format!("return items.find(item => {});", conditions.join(" && ")).as_str();
+    buf.unindent();
+    buf.new_line();
+    buf += "}";
+    buf.new_line();
+    Some(buf.flush())
+}
+
+/// The interfaces of the model `struc` implements, leaving out skipped ones.
+fn interfaces(struc: &Structure) -> Vec<String> {
+    let interfaces = struc.interfaces.iter().filter_map(Reference::get);
+    interfaces.filter(|i| !i.has_hint("typescript", "skip")).map(|i| i.identifier()).collect()
+}
+
+/// Writes the default `value` of `attr` as a TypeScript expression.
+fn initialiser(value: &Value, attr: &Attribute) -> String {
+    match value {
+        Value::String(string) => format!("{:?}", string),
+        Value::Member(member) => format!("{}.{}", attr.entity.entity().identifier(), member),
+        value => value.to_string(),
+    }
+}
+
+/// Renders the doc comments of the model as TSDoc, ending with a line break. Elements
+/// annotated with `@deprecated` get the `@deprecated` tag.
+fn tsdoc(docs: &Option<String>, element: &dyn Annotated) -> String {
+    let deprecated = element.annotation("deprecated").map(|deprecated| match &deprecated.value {
+        Some(reason) => format!("@deprecated {}", reason),
+        None => String::from("@deprecated"),
+    });
+    let docs = match (docs, deprecated) {
+        (Some(docs), Some(deprecated)) => format!("{}\n{}", docs, deprecated),
+        (Some(docs), None) => docs.clone(),
+        (None, Some(deprecated)) => deprecated,
+        (None, None) => return String::new(),
+    };
+    let docs = docs.replace("*/", "*\\/");
+    if docs.lines().count() == 1 {
+        return format!("/** {} */\n", docs);
+    }
+    let mut tsdoc = String::from("/**\n");
+    for line in docs.lines() {
+        tsdoc += format!(" * {}", line).trim_end();
+        tsdoc += "\n";
+    }
+    tsdoc + " */\n"
+}
+
+impl Transform<Package> for Declaration {
+    fn transform(model: &Package) -> Self {
         let mut flattened = vec![];
         //let typed: Vec<Typed> = model.entities.iter().map(|e| Typed::transform(e)).collect();
-        for entity in &model.entities {
-            let tp = Typed::transform(&entity);
-            if let Typed::Vec(vec) = tp {
-                for tp2 in vec {
-                    flattened.push(tp2);
+        for namespace in &model.namespaces {
+            for entity in &namespace.entities {
+                let source = entity.span().map(|span| namespace.locate(span));
+                let mut tp = Typed::transform(&entity);
+                if let Typed::Vec(vec) = tp {
+                    for mut tp2 in vec {
+                        tp2.set_source(source.clone());
+                        flattened.push(tp2);
+                    }
+                } else {
+                    tp.set_source(source);
+                    flattened.push(tp);
                 }
-            } else {
-                flattened.push(tp);
             }
         }
+        // Every event of the model, for code handling any of them.
+        let events: Vec<String> = model
+            .entities()
+            .iter()
+            .filter(|e| matches!(e.as_ref(), Entity::Structure(s) if s.is_event()))
+            .filter(|e| !e.has_hint("typescript", "skip"))
+            .map(|e| e.identifier())
+            .collect();
+        if !events.is_empty() {
+            flattened.push(Typed::Alias(Alias {
+                name: String::from("DomainEvent"),
+                docs: String::from("/** Any event of the model. */\n"),
+                source: None,
+                typ: events.join(" | "),
+            }));
+        }
 
         Declaration { typed: flattened }
     }
@@ -193,15 +639,23 @@ impl Transform<Namespace> for Declaration {
 
 impl Transform<Structure> for Interface {
     fn transform(model: &Structure) -> Self {
+        // Interfaces of the model extend the interfaces they implement, the interface of a class
+        // lists everything the class provides.
+        let (attributes, operations) = if model.is_interface() {
+            (model.attributes.clone(), model.operations.clone())
+        } else {
+            (model.effective_attributes(), model.effective_operations())
+        };
         let mut ops: Vec<String> = vec![];
-        for op in &model.operations {
+        for op in operations.iter().filter(|op| !op.has_hint("typescript", "skip")) {
             let mut buf = Buffer::default();
+            buf += tsdoc(&op.docs, op).as_str();
             buf += op.name.as_str();
             buf += "(";
             for (index, param) in op.parameter.iter().enumerate() {
                 buf += param.name.as_str();
                 buf += ": ";
-                buf += Typed::transform(param.entity.as_ref()).identifier().as_str();
+                buf += reference_name(&param.entity).as_str();
                 if index < op.parameter.len() - 1 {
                     buf += ",";
                 }
@@ -210,45 +664,110 @@ impl Transform<Structure> for Interface {
             ops.push(buf.flush());
         }
         let mut attrs = vec![];
-        for attr in &model.attributes {
+        for attr in attributes.iter().filter(|attr| !attr.has_hint("typescript", "skip")) {
             let mut buf = Buffer::default();
+            buf += tsdoc(&attr.docs, attr).as_str();
+            if attr.is_annotated("readonly") {
+                buf += "readonly ";
+            }
             buf += attr.name.as_str();
             buf += ": ";
-            buf += attr.entity.identifier().as_str();
+            buf += reference_name(&attr.entity).as_str();
             attrs.push(buf.flush());
         }
+        // Interfaces of the model keep their name, the others describe the class of a structure.
+        let (name, extends) = if model.is_interface() {
+            (model.name.clone(), Some(interfaces(model)).filter(|i| !i.is_empty()))
+        } else {
+            (String::from("I") + &model.name, None)
+        };
         Interface {
-            name: String::from("I") + &model.name,
+            name,
+            parameters: parameters(model),
+            docs: tsdoc(&model.docs, model),
+            source: None,
             operations: ops,
             attributes: attrs,
+            extends,
+        }
+    }
+}
+
+impl Transform<Service> for Interface {
+    fn transform(model: &Service) -> Self {
+        let mut ops: Vec<String> = vec![];
+        for op in model.operations.iter().filter(|op| !op.has_hint("typescript", "skip")) {
+            let mut buf = Buffer::default();
+            buf += tsdoc(&op.docs, op).as_str();
+            buf += op.name.as_str();
+            buf += "(";
+            for (index, param) in op.parameter.iter().enumerate() {
+                buf += param.name.as_str();
+                buf += ": ";
+                buf += reference_name(&param.entity).as_str();
+                if index < op.parameter.len() - 1 {
+                    buf += ", ";
+                }
+            }
+            // Unlike the operations of structures, services are implemented elsewhere, so their
+            // callers need to know what they return.
+            buf += "): ";
+            buf += op.returns.as_ref().map_or(String::from("void"), reference_name).as_str();
+            buf += ";";
+            ops.push(buf.flush());
+        }
+        Interface {
+            name: model.name.clone(),
+            parameters: String::new(),
+            docs: tsdoc(&model.docs, model),
+            source: None,
             extends: None,
+            attributes: vec![],
+            operations: ops,
         }
     }
 }
 
 impl Transform<Structure> for Class {
     fn transform(model: &Structure) -> Self {
+        // The class inherits from the class of its parent, so it only declares its own features
+        // and the ones of its interfaces no ancestor provides.
+        let ancestors = model.ancestors();
+        let inherited: Vec<&Structure> = ancestors
+            .iter()
+            .filter_map(|a| match a.as_ref() {
+                Entity::Structure(struc) => Some(struc),
+                _ => None,
+            })
+            .collect();
+        let operations: Vec<Operation> = model
+            .effective_operations()
+            .into_iter()
+            .filter(|op| {
+                let declares =
+                    |s: &Structure| s.operations.iter().any(|o| o.signature() == op.signature());
+                declares(model) || !inherited.iter().any(|s| declares(s))
+            })
+            .collect();
         let mut ops: Vec<String> = vec![];
-        for op in &model.operations {
+        for op in operations.iter().filter(|op| !op.has_hint("typescript", "skip")) {
             let mut buf = Buffer::default();
+            buf += tsdoc(&op.docs, op).as_str();
             buf += "public ";
             buf += op.name.as_str();
             buf += "(";
             for (index, param) in op.parameter.iter().enumerate() {
                 buf += param.name.as_str();
                 buf += ": ";
-                buf += Typed::transform(param.entity.as_ref()).identifier().as_str();
+                buf += reference_name(&param.entity).as_str();
                 if index < op.parameter.len() - 1 {
                     buf += ",";
                 }
             }
             buf += ")";
-            if let Some(ent) = &op.returns {
-                let typed = Typed::transform(ent);
-                if typed.is_some() {
-                    buf += ": ";
-                    buf += typed.identifier().as_str();
-                }
+            if let Some(returns) = &op.returns {
+                buf += ": ";
+                buf += reference_name(returns).as_str();
             }
             buf += " {";
             buf.indent();
@@ -265,22 +784,110 @@ impl Transform<Structure> for Class {
             ops.push(buf.flush());
         }
 
+        let effective = model.effective_attributes();
+        let effective: Vec<&Attribute> =
+            effective.iter().filter(|attr| !attr.has_hint("typescript", "skip")).collect();
+        let attributes: Vec<&Attribute> = effective
+            .iter()
+            .filter(|attr| {
+                let declares = |s: &Structure| s.attributes.iter().any(|a| a.name == attr.name);
+                declares(model) || !inherited.iter().any(|s| declares(s))
+            })
+            .copied()
+            .collect();
+        ops.extend(find_by_id(model));
+        // The validation of a class replaces the one of its parent, so it covers the inherited
+        // attributes as well.
+        if attributes.iter().any(|attr| !attr.constraints.is_empty()) {
+            ops.push(validate(effective.as_slice()));
+        }
+
         let mut attrs = vec![];
-        for attr in &model.attributes {
+        for attr in attributes {
             let mut buf = Buffer::default();
+            buf += tsdoc(&attr.docs, attr).as_str();
             buf += "public ";
+            if attr.is_annotated("readonly") {
+                buf += "readonly ";
+            }
             buf += attr.name.as_str();
             buf += ": ";
-            buf += attr.entity.identifier().as_str();
+            buf += reference_name(&attr.entity).as_str();
+            if let Some(default) = &attr.default {
+                buf += " = ";
+                buf += initialiser(default, attr).as_str();
+            }
             attrs.push(buf.flush());
         }
 
         Class {
             name: model.identifier(),
+            parameters: parameters(model),
+            docs: tsdoc(&model.docs, model),
+            source: None,
+            is_abstract: model.kind == StructureKind::Abstract,
             extends: model.parent.as_ref().map(|p| p.identifier()),
             operations: ops,
             attributes: attrs,
-            implements: Some(vec![String::from("I") + &model.name]),
+            implements: Some(
+                [vec![format!("I{}{}", model.name, parameters(model))], interfaces(model)].concat(),
+            ),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::typescript::Declaration;
+    use ast::Package;
+    use generator::Transform;
+    use parser::{parse, parse_from_file};
+    use util::code::CodeFragment;
+
+    /// Generates the declarations of `package`, leaving out the comments marking the fragments.
+    fn generate(package: &Package) -> String {
+        let page = Declaration::transform(package).generate();
+        let fragments: Vec<String> = page
+            .fragments
+            .into_iter()
+            .filter_map(|fragment| match fragment {
+                CodeFragment::Generated(generated) => Some(generated.code),
+                _ => None,
+            })
+            .collect();
+        fragments.join("\n\n")
+    }
+
+    #[test]
+    fn test_example() {
+        let package = parse_from_file("../parser/test/example.pakken").expect("Should have parsed");
+        let declarations = generate(&package);
+        assert!(declarations.contains("export enum Enum {\n\tvalue1 = 2,\n\tvalue2,\n}"));
+    }
+
+    #[test]
+    fn test_enums() {
+        let code = "org.a {\n    /// How urgent.\n    Priority { unknown = -1, high }\n    Color \
+                    {\n  /// Pure red.\n        red = \"#f00\",\n        @deprecated(\"use red\") \
+                    green,\n        @typescript(skip) blue\n    }\n    Size { s, \
+                    @typescript(skip) m, l, xl }\n    A { p: Priority = high }\n}";
+        let package = parse(code).expect("Should have parsed");
+        let declarations = generate(&package);
+        let priority =
+            ["/** How urgent. */", "export enum Priority {", "\tunknown = -1,", "\thigh,", "}"];
+        assert!(declarations.contains(priority.join("\n").as_str()));
+        let color = [
+            "export enum Color {",
+            "\t/** Pure red. */",
+            "\tred = \"#f00\",",
+            "\t/** @deprecated use red */",
+            "\tgreen = \"green\",",
+            "}",
+        ];
+        assert!(declarations.contains(color.join("\n").as_str()));
+        // The members after a skipped member keep their values.
+        let size = ["export enum Size {", "\ts,", "\tl = 2,", "\txl = 3,", "}"];
+        assert!(declarations.contains(size.join("\n").as_str()));
+        assert!(declarations.contains("public p: Priority = Priority.high;"));
+    }
+}
@@ -9,6 +9,10 @@ pub struct Buffer {
 impl Buffer {
     pub fn default() -> Buffer { Buffer { buffer: String::new(), indent_string: "\t", indents: 0 } }
 
+    pub fn with_indent(indent_string: &'static str) -> Buffer {
+        Buffer { buffer: String::new(), indent_string, indents: 0 }
+    }
+
     pub fn indent(&mut self) { self.indents += 1; }
 
     pub fn unindent(&mut self) { self.indents -= 1; }
@@ -18,6 +18,10 @@ pub enum PakError {
     TargetNotFound(String),
     /// parser error: {0}
     ParserError(String),
+    /// the model contains errors.
+    Diagnostics(Vec<String>),
+    /// not formatted: {0}
+    NotFormatted(String),
 }
 
 impl From<io::Error> for PakError {
@@ -9,6 +9,7 @@ use std::path::{Path, PathBuf};
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Project {
     pub name: String,
+    /// The root model file. Files imported by it are loaded relative to it.
     pub model: PathBuf,
 }
 
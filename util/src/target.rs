@@ -5,6 +5,9 @@ use std::any::Any;
 
 pub trait Target: Any + Send {
     fn name(&self) -> &'static str;
+    /// The names of the annotations of the model this target reads, e.g. `deprecated`. Other
+    /// annotations are reported as ignored when generating.
+    fn annotations(&self) -> &'static [&'static str] { &[] }
     fn generate_from(&self, model: Model) -> PakResult<FileStructure>;
 }
 